@@ -0,0 +1,26 @@
+//! Measures how fast boards are copied and searched, to compare the storages of the columns: run
+//! it with `cargo bench --bench columns`, then again with `--features smallvec`.
+
+use std::time::Instant;
+
+use sorting::Puzzle;
+
+fn main() {
+    let boards: Vec<Puzzle> = (0..20)
+        .map(|seed| Puzzle::generate_seeded(4, 8, 2, seed))
+        .collect();
+
+    let start = Instant::now();
+    let nodes: u64 = boards
+        .iter()
+        .map(|p| p.solve_with_stats(5, 100).1.nodes_visited)
+        .sum();
+    let rate = nodes as f64 / start.elapsed().as_secs_f64() / 1e6;
+    println!("solve(5, 100) on 20 boards: {rate:.2} million nodes per second");
+
+    let start = Instant::now();
+    for p in &boards[..10] {
+        p.solve_astar();
+    }
+    println!("solve_astar on 10 boards: {:?}", start.elapsed());
+}
@@ -0,0 +1,31 @@
+//! Compares the sequential search with the one spread over the rayon pool: run it with
+//! `cargo bench --bench rayon --features rayon` on a machine with several cores.
+
+use std::time::Instant;
+
+use sorting::Puzzle;
+
+fn main() {
+    let boards: Vec<Puzzle> = (0..20)
+        .map(|seed| Puzzle::generate_seeded(4, 10, 2, seed))
+        .collect();
+
+    let start = Instant::now();
+    let sequential: Vec<_> = boards.iter().map(|p| p.solve(5, 100)).collect();
+    let sequential_time = start.elapsed();
+
+    let start = Instant::now();
+    let pooled: Vec<_> = boards.iter().map(|p| p.solve_rayon(5, 100)).collect();
+    let pooled_time = start.elapsed();
+
+    assert_eq!(
+        pooled, sequential,
+        "solve_rayon must return the moves of solve"
+    );
+    println!(
+        "solve(5, 100) on 20 boards: {sequential_time:?}, solve_rayon: {pooled_time:?} ({:.2}x) \
+         on {} threads",
+        sequential_time.as_secs_f64() / pooled_time.as_secs_f64(),
+        rayon::current_num_threads()
+    );
+}
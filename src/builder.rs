@@ -0,0 +1,106 @@
+use std::hash::Hash;
+
+use crate::{Puzzle, PuzzleError};
+
+/// Builds a [`Puzzle`] one column at a time, returned by [`Puzzle::builder`].
+#[derive(Debug, Clone)]
+pub struct PuzzleBuilder<C> {
+    column_size: Option<usize>,
+    columns: Vec<Vec<C>>,
+    /// First error met while adding columns, returned by `build`.
+    error: Option<PuzzleError>,
+}
+
+impl<C: Copy + Eq + Hash> Puzzle<C> {
+    /// Starts building a puzzle, e.g.
+    /// `Puzzle::builder().column_size(4).column([1, 2, 3, 4]).column([]).build()`.
+    pub fn builder() -> PuzzleBuilder<C> {
+        PuzzleBuilder {
+            column_size: None,
+            columns: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+impl<C: Copy + Eq + Hash> PuzzleBuilder<C> {
+    /// Sets the number of balls each column can hold. Without it, the columns are as large as
+    /// the longest one.
+    pub fn column_size(mut self, size: usize) -> Self {
+        self.column_size = Some(size);
+        self
+    }
+
+    /// Adds a column holding `balls`, from bottom to top.
+    pub fn column(mut self, balls: impl IntoIterator<Item = C>) -> Self {
+        let balls: Vec<C> = balls.into_iter().collect();
+        if let Some(size) = self.column_size
+            && balls.len() > size
+            && self.error.is_none()
+        {
+            self.error = Some(PuzzleError::Overfilled {
+                column: self.columns.len(),
+                len: balls.len(),
+                capacity: size,
+            });
+        }
+        self.columns.push(balls);
+        self
+    }
+
+    /// Returns the puzzle, or the first error met while adding the columns. Columns added before
+    /// the column size was set are checked here, along with everything [`Puzzle::try_new`]
+    /// checks.
+    pub fn build(self) -> Result<Puzzle<C>, PuzzleError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let size = self
+            .column_size
+            .unwrap_or_else(|| self.columns.iter().map(Vec::len).max().unwrap_or(0));
+        Puzzle::try_new(size, &self.columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_columns_in_order() {
+        let p = Puzzle::builder()
+            .column_size(4)
+            .column([1, 2, 3, 4])
+            .column([4, 3, 2, 1])
+            .column([])
+            .build()
+            .unwrap();
+        assert_eq!(
+            p.state,
+            Puzzle::new(4, &[vec![1, 2, 3, 4], vec![4, 3, 2, 1], vec![]]).state
+        );
+        assert_eq!(p.column_size(), 4);
+    }
+
+    #[test]
+    fn rejects_overfilled_columns() {
+        let overfilled = PuzzleError::Overfilled {
+            column: 1,
+            len: 3,
+            capacity: 2,
+        };
+        let built = Puzzle::builder()
+            .column_size(2)
+            .column([1, 2])
+            .column([2, 1, 1])
+            .build();
+        assert_eq!(built.unwrap_err(), overfilled);
+        // Columns added before the size is set are checked by `build`.
+        let built = Puzzle::builder()
+            .column([1, 2])
+            .column([2, 1, 1])
+            .column_size(2)
+            .build();
+        assert_eq!(built.unwrap_err(), overfilled);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{Capacity, Color, MoveMode, Puzzle, PuzzleError};
+
+/// Flags making up the first number of a code.
+const PER_COLUMN: u64 = 1;
+const JOKER: u64 = 2;
+const SINGLE_BALL: u64 = 4;
+
+/// Digits of the URL-safe base64 alphabet.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Appends `n` in LEB128: 7 bits per byte, the high bit telling whether more bytes follow.
+fn push_varint(out: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Reads a number written by `push_varint`, or `None` if the bytes run out or it overflows.
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<u64> {
+    let mut n: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = bytes.next()?;
+        n |= u64::from(byte & 0x7f).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(n);
+        }
+    }
+    None
+}
+
+/// Same as `read_varint`, for sizes and counts.
+fn read_usize(bytes: &mut impl Iterator<Item = u8>) -> Option<usize> {
+    usize::try_from(read_varint(bytes)?).ok()
+}
+
+/// Encodes `bytes` in base64 with the URL-safe alphabet, without padding.
+fn encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Decodes the output of `encode`, or returns `None` if `code` holds other characters or
+/// couldn't have been produced by it.
+fn decode(code: &str) -> Option<Vec<u8>> {
+    let digits = code
+        .bytes()
+        .map(|c| ALPHABET.iter().position(|&d| d == c).map(|d| d as u32))
+        .collect::<Option<Vec<_>>>()?;
+    let mut out = Vec::new();
+    for chunk in digits.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, &d)| n | d << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
+
+impl Puzzle {
+    /// Returns a short code describing the board, made of URL-safe characters, e.g. to share it
+    /// in a link. [`Puzzle::from_code`] reads it back. The history of the board is not included.
+    ///
+    /// The code is the base64 encoding of a list of variable-length numbers: flags telling
+    /// whether the columns have sizes of their own, whether there is a joker and whether moves
+    /// pour a single ball, the size of the columns or the size of each one after their number,
+    /// the color of the joker if any, then the length of each column followed by its balls,
+    /// bottom to top.
+    pub fn to_code(&self) -> String {
+        let mut bytes = Vec::new();
+        let mut flags = 0;
+        if matches!(self.capacity, Capacity::PerColumn(_)) {
+            flags |= PER_COLUMN;
+        }
+        if self.joker.is_some() {
+            flags |= JOKER;
+        }
+        if self.move_mode == MoveMode::Single {
+            flags |= SINGLE_BALL;
+        }
+        push_varint(&mut bytes, flags);
+        match &self.capacity {
+            Capacity::Uniform(size) => {
+                push_varint(&mut bytes, *size as u64);
+                push_varint(&mut bytes, self.state.len() as u64);
+            }
+            Capacity::PerColumn(sizes) => {
+                push_varint(&mut bytes, self.state.len() as u64);
+                for &size in sizes {
+                    push_varint(&mut bytes, size as u64);
+                }
+            }
+        }
+        if let Some(joker) = self.joker {
+            push_varint(&mut bytes, joker.into());
+        }
+        for col in &self.state {
+            push_varint(&mut bytes, col.len() as u64);
+            for &c in col {
+                push_varint(&mut bytes, c.into());
+            }
+        }
+        encode(&bytes)
+    }
+
+    /// Reads a board from a code produced by [`Puzzle::to_code`]. Fails with
+    /// [`PuzzleError::InvalidCode`] if the text is not such a code, or with the same errors as
+    /// [`Puzzle::try_new`] if it describes an invalid board.
+    pub fn from_code(code: &str) -> Result<Puzzle, PuzzleError> {
+        let bytes = decode(code).ok_or(PuzzleError::InvalidCode)?;
+        let board = read_board(&mut bytes.into_iter()).ok_or(PuzzleError::InvalidCode)?;
+        let mut puzzle = Puzzle::try_new(board.capacity, &board.columns)?;
+        puzzle.set_joker(board.joker);
+        puzzle.set_move_mode(board.move_mode);
+        Ok(puzzle)
+    }
+}
+
+/// Board described by a code, before it is checked.
+struct CodeBoard {
+    capacity: Capacity,
+    columns: Vec<Vec<Color>>,
+    joker: Option<Color>,
+    move_mode: MoveMode,
+}
+
+/// Reads the numbers written by [`Puzzle::to_code`], or returns `None` if they are cut short or
+/// followed by anything.
+fn read_board(bytes: &mut impl Iterator<Item = u8>) -> Option<CodeBoard> {
+    let flags = read_varint(bytes)?;
+    if flags & !(PER_COLUMN | JOKER | SINGLE_BALL) != 0 {
+        return None;
+    }
+    let (capacity, num_columns) = if flags & PER_COLUMN == 0 {
+        let size = read_usize(bytes)?;
+        (Capacity::Uniform(size), read_usize(bytes)?)
+    } else {
+        let num_columns = read_usize(bytes)?;
+        let sizes = (0..num_columns)
+            .map(|_| read_usize(bytes))
+            .collect::<Option<_>>()?;
+        (Capacity::PerColumn(sizes), num_columns)
+    };
+    let joker = match flags & JOKER {
+        0 => None,
+        _ => Some(Color::try_from(read_varint(bytes)?).ok()?),
+    };
+    let columns = (0..num_columns)
+        .map(|_| {
+            let len = read_usize(bytes)?;
+            (0..len)
+                .map(|_| Color::try_from(read_varint(bytes)?).ok())
+                .collect()
+        })
+        .collect::<Option<_>>()?;
+    match bytes.next() {
+        Some(_) => None,
+        None => Some(CodeBoard {
+            capacity,
+            columns,
+            joker,
+            move_mode: match flags & SINGLE_BALL {
+                0 => MoveMode::Run,
+                _ => MoveMode::Single,
+            },
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_huge_sizes_without_reserving_them() {
+        let size = 100_000_000_000_000_000;
+        let p = Puzzle::new(size, &[vec![1], vec![]]);
+        let q = Puzzle::from_code(&p.to_code()).unwrap();
+        assert_eq!(q.column_size(), size);
+        assert_eq!(q.column(0), [1]);
+    }
+
+    #[test]
+    fn reads_back_the_main_board() {
+        let p = crate::tests::main_board();
+        let code = p.to_code();
+        assert!(code.bytes().all(|b| ALPHABET.contains(&b)), "{code}");
+        assert_eq!(Puzzle::from_code(&code), Ok(p));
+    }
+
+    #[test]
+    fn rejects_broken_codes() {
+        assert_eq!(Puzzle::from_code("AB*D"), Err(PuzzleError::InvalidCode));
+        assert_eq!(Puzzle::from_code("A"), Err(PuzzleError::InvalidCode));
+
+        let code = crate::tests::main_board().to_code();
+        for len in 0..code.len() {
+            assert_eq!(
+                Puzzle::from_code(&code[..len]),
+                Err(PuzzleError::InvalidCode),
+                "{len}"
+            );
+        }
+
+        // One column of size 2 holding three balls.
+        let overfilled = encode(&[0, 2, 1, 3, 1, 1, 1]);
+        assert_eq!(
+            Puzzle::from_code(&overfilled),
+            Err(PuzzleError::Overfilled {
+                column: 0,
+                len: 3,
+                capacity: 2
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_the_joker() {
+        let mut p = Puzzle::new(vec![3, 2, 2], &[vec![1, 9, 2], vec![2, 1], vec![]]);
+        p.set_joker(Some(9));
+        let q = Puzzle::from_code(&p.to_code()).unwrap();
+        assert_eq!(q, p);
+        assert_eq!(q.joker(), Some(9));
+
+        p.set_joker(None);
+        assert_eq!(Puzzle::from_code(&p.to_code()).unwrap().joker(), None);
+    }
+
+    #[test]
+    fn keeps_the_move_mode() {
+        let mut p = Puzzle::new(4, &[vec![1, 2, 2], vec![2, 1], vec![]]);
+        assert_eq!(
+            Puzzle::from_code(&p.to_code()).unwrap().move_mode(),
+            MoveMode::Run
+        );
+        p.set_move_mode(MoveMode::Single);
+        let q = Puzzle::from_code(&p.to_code()).unwrap();
+        assert_eq!(q, p);
+        assert_eq!(q.move_mode(), MoveMode::Single);
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Capacity, Color, Puzzle, PuzzleError, color_token};
+
+/// Names given to the colors of a board, such as `red` or `blue`, and the ids standing for them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColorMap {
+    names: Vec<String>,
+    ids: HashMap<String, Color>,
+}
+
+impl ColorMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of the color called `name`, giving it the next free id if it has none yet.
+    pub fn insert(&mut self, name: &str) -> Color {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len() as Color;
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        id
+    }
+
+    /// Returns the id of the color called `name`, if any.
+    pub fn id(&self, name: &str) -> Option<Color> {
+        self.ids.get(name).copied()
+    }
+
+    /// Returns the name of color `c`, if any.
+    pub fn name(&self, c: Color) -> Option<&str> {
+        self.names.get(c as usize).map(String::as_str)
+    }
+
+    /// Returns the number of named colors.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// Displays a [`Puzzle`] with the names of its colors, returned by [`Puzzle::display_named`].
+pub struct NamedDisplay<'a> {
+    puzzle: &'a Puzzle,
+    names: &'a ColorMap,
+}
+
+impl fmt::Display for NamedDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.puzzle.fmt_grid(f, |c| match self.names.name(c) {
+            Some(name) => name.to_owned(),
+            None => color_token(c),
+        })
+    }
+}
+
+impl Puzzle {
+    /// Same as [`Puzzle::try_new`], with the balls given by the names of their colors. Ids are
+    /// given to the colors in order of appearance, starting from 0, and returned along with the
+    /// puzzle so that it can be printed with names again.
+    pub fn from_named(
+        capacity: impl Into<Capacity>,
+        init: &[Vec<&str>],
+    ) -> Result<(Puzzle, ColorMap), PuzzleError> {
+        let mut names = ColorMap::new();
+        let columns: Vec<Vec<Color>> = init
+            .iter()
+            .map(|col| col.iter().map(|name| names.insert(name)).collect())
+            .collect();
+        let puzzle = Puzzle::try_new(capacity, &columns)?;
+        Ok((puzzle, names))
+    }
+
+    /// Returns an object displaying the board like `Display`, with colors printed with their
+    /// names in `names`. Colors without a name are printed as usual.
+    pub fn display_named<'a>(&'a self, names: &'a ColorMap) -> NamedDisplay<'a> {
+        NamedDisplay {
+            puzzle: self,
+            names,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_boards_print_their_names() {
+        let (p, names) =
+            Puzzle::from_named(2, &[vec!["red", "blue"], vec!["blue", "red"], vec![]]).unwrap();
+        assert_eq!(p.column(0), [0, 1]);
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.id("blue"), Some(1));
+        assert_eq!(names.name(1), Some("blue"));
+        assert_eq!(names.name(2), None);
+        assert_eq!(
+            p.display_named(&names).to_string(),
+            "[blue] [ red] [    ]\n[ red] [blue] [    ]"
+        );
+
+        let mut more = names.clone();
+        assert_eq!(more.insert("red"), 0);
+        assert_eq!(more.insert("green"), 2);
+        assert_eq!(more.len(), 3);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{Puzzle, SearchColor};
+
+/// How hard a puzzle is to solve, as estimated by [`Puzzle::difficulty`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Difficulty {
+    Trivial,
+    Easy,
+    Medium,
+    Hard,
+    /// Either extremely long to solve or not solvable at all.
+    BrutalUnsolvable,
+}
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Estimates how hard the puzzle is from the length of its optimal solution, the number of
+    /// buried balls (sitting under a ball of another color) and how much free space there is.
+    ///
+    /// The effort is the optimal number of moves plus the number of buried balls, increased by
+    /// half when there are fewer free slots than a quarter of the number of balls. It maps to:
+    ///
+    /// - `Trivial`: below 6, which includes solved boards,
+    /// - `Easy`: below 20,
+    /// - `Medium`: below 40,
+    /// - `Hard`: below 70,
+    /// - `BrutalUnsolvable`: anything above, or if the puzzle cannot be solved.
+    ///
+    /// This solves the puzzle optimally, so it gets slow on large boards.
+    pub fn difficulty(&self) -> Difficulty {
+        if self.is_solved() {
+            return Difficulty::Trivial;
+        }
+        let Some(solution) = self.solve_astar() else {
+            return Difficulty::BrutalUnsolvable;
+        };
+
+        let buried = self.buried_count();
+        let balls: usize = self.state.iter().map(|col| col.len()).sum();
+        let free: usize = (0..self.state.len()).map(|i| self.free_space(i)).sum();
+
+        let mut effort = solution.len() + buried;
+        if free * 4 < balls {
+            effort += effort / 2;
+        }
+        match effort {
+            0..6 => Difficulty::Trivial,
+            6..20 => Difficulty::Easy,
+            20..40 => Difficulty::Medium,
+            40..70 => Difficulty::Hard,
+            _ => Difficulty::BrutalUnsolvable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longer_solutions_rate_harder() {
+        let solved = Puzzle::new(2, &[vec![1, 1], vec![]]);
+        assert_eq!(solved.difficulty(), Difficulty::Trivial);
+        let easy = Puzzle::new(2, &[vec![1, 2], vec![2, 1], vec![]]);
+        let hard = crate::tests::main_board();
+        assert!(easy.difficulty() < hard.difficulty());
+        let stuck = Puzzle::new(2, &[vec![1, 2], vec![2, 1]]);
+        assert_eq!(stuck.difficulty(), Difficulty::BrutalUnsolvable);
+    }
+}
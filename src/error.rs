@@ -0,0 +1,172 @@
+use std::error;
+use std::fmt;
+
+use crate::Color;
+
+/// Error returned by the fallible operations on a [`Puzzle`](crate::Puzzle).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    /// The index does not refer to a column of the board.
+    NoSuchColumn(usize),
+    /// The move tries to pour from an empty column.
+    EmptyColumn(usize),
+    /// The move tries to pour into a column that has no free slot.
+    ColumnFull(usize),
+    /// The top balls of the two columns of the move have different colors.
+    ColorMismatch { from: usize, to: usize },
+    /// The move pours a column into itself.
+    SameColumn(usize),
+    /// A column holds more balls than the column size allows.
+    Overfilled {
+        column: usize,
+        len: usize,
+        capacity: usize,
+    },
+    /// The number of column sizes doesn't match the number of columns.
+    CapacityCount { columns: usize, capacities: usize },
+    /// The recorded number of balls of a color doesn't match the board.
+    CountMismatch {
+        color: Color,
+        expected: usize,
+        found: usize,
+    },
+    /// Move `index` of a solution cannot be played.
+    IllegalStep {
+        index: usize,
+        error: Box<PuzzleError>,
+    },
+    /// All the moves of a solution were played but the board is not solved.
+    NotSolved { moves: usize },
+    /// A row of a matrix doesn't have as many cells as the first one.
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A ball of a matrix sits above an empty slot.
+    FloatingBall { row: usize, column: usize },
+    /// The text is not a code produced by [`Puzzle::to_code`](crate::Puzzle::to_code).
+    InvalidCode,
+    /// Move `index` of a list is not written as `from->to`.
+    InvalidMoveNotation { index: usize },
+    /// The input is not a JSON description of a board, with the message of the parser.
+    #[cfg(feature = "serde")]
+    Json(String),
+    /// Move `index` of a saved history cannot have been played to reach the saved board.
+    #[cfg(feature = "serde")]
+    InvalidHistory { index: usize },
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchColumn(i) => write!(f, "there is no column {i}"),
+            Self::EmptyColumn(i) => write!(f, "column {i} is empty"),
+            Self::ColumnFull(i) => write!(f, "column {i} is full"),
+            Self::ColorMismatch { from, to } => {
+                write!(f, "the top colors of columns {from} and {to} differ")
+            }
+            Self::SameColumn(i) => write!(f, "cannot pour column {i} into itself"),
+            Self::Overfilled {
+                column,
+                len,
+                capacity,
+            } => write!(
+                f,
+                "column {column} has {len} balls but can only hold {capacity}"
+            ),
+            Self::CapacityCount {
+                columns,
+                capacities,
+            } => write!(f, "{capacities} column sizes given for {columns} columns"),
+            Self::CountMismatch {
+                color,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {expected} balls of color {color}, found {found}"
+            ),
+            Self::IllegalStep { index, error } => write!(f, "move {index} is illegal: {error}"),
+            Self::NotSolved { moves } => {
+                write!(f, "the board is not solved after {moves} moves")
+            }
+            Self::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {row} has {found} cells, expected {expected}"),
+            Self::FloatingBall { row, column } => {
+                write!(f, "ball above an empty slot at row {row}, column {column}")
+            }
+            Self::InvalidCode => write!(f, "not a valid board code"),
+            Self::InvalidMoveNotation { index } => {
+                write!(f, "move {index} is not written as from->to")
+            }
+            #[cfg(feature = "serde")]
+            Self::Json(message) => write!(f, "invalid JSON: {message}"),
+            #[cfg(feature = "serde")]
+            Self::InvalidHistory { index } => {
+                write!(f, "move {index} of the history doesn't lead to the board")
+            }
+        }
+    }
+}
+
+impl error::Error for PuzzleError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IllegalStep { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Error returned when a textual grid cannot be parsed back into a [`Puzzle`](crate::Puzzle).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePuzzleError {
+    /// The input does not contain any row.
+    Empty,
+    /// A row is not a sequence of `[c]` cells separated by single spaces.
+    Malformed { row: usize },
+    /// A row does not have the same number of cells as the first one.
+    Ragged {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A cell holds something that is neither a color nor a blank.
+    InvalidColor { row: usize, column: usize },
+    /// A ball is sitting above an empty slot of its column.
+    Floating { row: usize, column: usize },
+    /// A slot is sitting above the top of its column.
+    MissingSlot { row: usize, column: usize },
+}
+
+impl fmt::Display for ParsePuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty puzzle"),
+            Self::Malformed { row } => write!(f, "row {row} is not a sequence of [c] cells"),
+            Self::Ragged {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {row} has {found} cells, expected {expected}"),
+            Self::InvalidColor { row, column } => {
+                write!(f, "invalid color at row {row}, column {column}")
+            }
+            Self::Floating { row, column } => {
+                write!(f, "ball above an empty slot at row {row}, column {column}")
+            }
+            Self::MissingSlot { row, column } => {
+                write!(
+                    f,
+                    "slot above the top of its column at row {row}, column {column}"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for ParsePuzzleError {}
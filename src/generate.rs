@@ -0,0 +1,160 @@
+use rand::rngs::ChaCha8Rng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+
+use crate::zobrist::ZobristHash;
+use crate::{Color, Move, Puzzle};
+
+impl Puzzle {
+    /// Returns the pours that could have led to this board: playing any of them on the board
+    /// obtained with `undo_move(m, m.count)` pours exactly `m.count` balls and brings back this
+    /// board.
+    fn reverse_moves(&self) -> Vec<Move> {
+        let mut board = self.without_history();
+        let mut moves = Vec::new();
+        for to in 0..self.state.len() {
+            for from in (0..self.state.len()).filter(|&from| from != to) {
+                for count in 1..=self.top_run_length(to).min(self.free_space(from)) {
+                    let m = Move { from, to, count };
+                    // Pouring the balls back may take those below them too, or be impossible
+                    // once they lie on another color.
+                    board.undo_move(m, count);
+                    if board.pour_size(from, to) == count {
+                        moves.push(m);
+                    }
+                    board.undo_move(m.inverse(count), count);
+                }
+            }
+        }
+        moves
+    }
+
+    /// Returns the solved board with `num_colors` full columns of a single color each, ids
+    /// starting from 0 in column order, followed by `num_empty` empty columns.
+    pub fn from_solved(column_size: usize, num_colors: usize, num_empty: usize) -> Puzzle {
+        let columns: Vec<Vec<Color>> = (0..num_colors as Color)
+            .map(|c| vec![c; column_size])
+            .chain((0..num_empty).map(|_| Vec::new()))
+            .collect();
+        Puzzle::new(column_size, &columns)
+    }
+
+    /// Scrambles the board by taking back up to `moves` random pours, fewer if it runs out of
+    /// them. Returns the scrambled board along with the pours, oldest first: playing them with
+    /// [`Puzzle::do_move`] from the last one back to the first leads back to this board, so a
+    /// scramble of a solved board can always be solved.
+    pub fn scramble<R: Rng + ?Sized>(&self, moves: usize, rng: &mut R) -> (Puzzle, Vec<Move>) {
+        let mut puzzle = self.without_history();
+        let mut pours = Vec::new();
+        for _ in 0..moves {
+            let Some(&m) = puzzle.reverse_moves().choose(rng) else {
+                break;
+            };
+            puzzle.undo_move(m, m.count);
+            pours.push(m);
+        }
+        (puzzle, pours)
+    }
+
+    /// Generates a random puzzle with `num_colors` colors of `column_size` balls each, ids
+    /// starting from 0, and `num_empty` extra columns. It starts with every color in its own full
+    /// column and the extra columns empty, like a freshly dealt board.
+    ///
+    /// The puzzle is scrambled by undoing random pours from its solved state, so it is always
+    /// solvable. Walks that end up with partially filled columns are thrown away, unless no
+    /// regular board can be found, for example with no spare column to scramble into. The columns
+    /// are shuffled at the end.
+    pub fn generate<R: Rng + ?Sized>(
+        column_size: usize,
+        num_colors: usize,
+        num_empty: usize,
+        rng: &mut R,
+    ) -> Puzzle {
+        const ATTEMPTS: usize = 100;
+
+        let solved = Puzzle::from_solved(column_size, num_colors, num_empty);
+        let steps = 4 * num_colors * column_size;
+        let mut puzzle = solved.clone();
+        for _ in 0..ATTEMPTS {
+            (puzzle, _) = solved.scramble(steps, rng);
+            let regular = puzzle
+                .state
+                .iter()
+                .all(|col| col.is_empty() || col.len() == column_size);
+            if regular && !puzzle.is_solved() {
+                break;
+            }
+        }
+        puzzle.state.shuffle(rng);
+        puzzle.zobrist = ZobristHash::new(&puzzle.state, &puzzle.capacity);
+        puzzle
+    }
+
+    /// Same as [`Puzzle::generate`], with the randomness drawn from `seed`. A given seed always
+    /// yields the same board, and that board is guaranteed not to change across patch releases,
+    /// so seeds can be used to share boards or in regression tests. The generator is ChaCha8,
+    /// whose output rand guarantees, unlike that of its `StdRng`.
+    pub fn generate_seeded(
+        column_size: usize,
+        num_colors: usize,
+        num_empty: usize,
+        seed: u64,
+    ) -> Puzzle {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        Self::generate(column_size, num_colors, num_empty, &mut rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MoveMode;
+
+    #[test]
+    fn seeds_always_give_the_same_board() {
+        let a = Puzzle::generate_seeded(4, 6, 2, 19);
+        let b = Puzzle::generate_seeded(4, 6, 2, 19);
+        assert_eq!(a.state, b.state);
+
+        // Boards shared by their seed must stay the same from one release to the next.
+        let p = Puzzle::generate_seeded(3, 3, 1, 19);
+        let expected = Puzzle::new(3, &[vec![2, 2, 0], vec![0, 1, 2], vec![1, 1, 0], vec![]]);
+        assert_eq!(p.state, expected.state);
+    }
+
+    #[test]
+    fn scrambles_are_solved_and_replay_back() {
+        let mut rng = ChaCha8Rng::seed_from_u64(96);
+        for joker in [None, Some(0)] {
+            for mode in [MoveMode::Run, MoveMode::Single] {
+                let mut solved = Puzzle::from_solved(4, 4, 2);
+                solved.set_joker(joker);
+                solved.set_move_mode(mode);
+                for _ in 0..20 {
+                    let (mut p, pours) = solved.scramble(30, &mut rng);
+                    // Jokers multiply the lines to search, which makes solving too slow here.
+                    if joker.is_none() {
+                        let moves = Vec::from(p.solve(5, 100));
+                        assert_eq!(p.verify_solution(&moves), Ok(()));
+                    }
+                    for &m in pours.iter().rev() {
+                        assert_eq!(p.do_move(m), Ok(m.count));
+                    }
+                    assert_eq!(p.state, solved.state);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_boards_are_solvable() {
+        let mut rng = ChaCha8Rng::seed_from_u64(18);
+        for _ in 0..10 {
+            let p = Puzzle::generate(3, 4, 2, &mut rng);
+            assert_eq!(p.num_colors(), 4);
+            assert_eq!(p.num_columns(), 6);
+            let moves = p.solve_bfs().expect("generated boards are solvable");
+            assert_eq!(p.verify_solution(&Vec::from(moves)), Ok(()));
+        }
+    }
+}
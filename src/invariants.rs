@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crate::{Capacity, Color, Puzzle, PuzzleError, recount};
+
+/// Fails with [`PuzzleError::CountMismatch`] on the first color whose number of balls in
+/// `found` isn't the one in `expected`, colors missing from either counting as 0.
+pub(crate) fn check_counts(
+    expected: &HashMap<Color, usize>,
+    found: &HashMap<Color, usize>,
+) -> Result<(), PuzzleError> {
+    let mut colors: Vec<Color> = expected.keys().chain(found.keys()).copied().collect();
+    colors.sort_unstable();
+    colors.dedup();
+    for color in colors {
+        let expected = expected.get(&color).copied().unwrap_or(0);
+        let found = found.get(&color).copied().unwrap_or(0);
+        if expected != found {
+            return Err(PuzzleError::CountMismatch {
+                color,
+                expected,
+                found,
+            });
+        }
+    }
+    Ok(())
+}
+
+impl Puzzle {
+    /// Checks that the board is consistent: there is a size for each column, no column holds
+    /// more balls than its size, and the recorded number of balls of each color matches the
+    /// board. This always holds for boards built and played through the public methods, so
+    /// a failure means one of them is broken, which makes this useful after each step of tests
+    /// and fuzzers.
+    pub fn check_invariants(&self) -> Result<(), PuzzleError> {
+        if let Capacity::PerColumn(sizes) = &self.capacity
+            && sizes.len() != self.state.len()
+        {
+            return Err(PuzzleError::CapacityCount {
+                columns: self.state.len(),
+                capacities: sizes.len(),
+            });
+        }
+        for (column, col) in self.state.iter().enumerate() {
+            let capacity = self.capacity.of(column);
+            if col.len() > capacity {
+                return Err(PuzzleError::Overfilled {
+                    column,
+                    len: col.len(),
+                    capacity,
+                });
+            }
+        }
+        check_counts(&self.colors_count, &recount(&self.state, self.joker))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupted_boards_fail_the_check() {
+        let p = crate::tests::main_board();
+        assert_eq!(p.check_invariants(), Ok(()));
+
+        let mut overfilled = p.clone();
+        overfilled.state[6].extend([1, 1, 1, 1, 1]);
+        overfilled.colors_count.insert(1, 9);
+        assert_eq!(
+            overfilled.check_invariants(),
+            Err(PuzzleError::Overfilled {
+                column: 6,
+                len: 5,
+                capacity: 4,
+            })
+        );
+
+        let mut miscounted = p.clone();
+        miscounted.colors_count.insert(3, 5);
+        assert_eq!(
+            miscounted.check_invariants(),
+            Err(PuzzleError::CountMismatch {
+                color: 3,
+                expected: 5,
+                found: 4,
+            })
+        );
+
+        let mut missing_size = Puzzle::new(vec![2, 2], &[vec![1, 1], vec![]]);
+        missing_size.state.push(Default::default());
+        assert_eq!(
+            missing_size.check_invariants(),
+            Err(PuzzleError::CapacityCount {
+                columns: 3,
+                capacities: 2,
+            })
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Capacity, Color, Move, MoveMode, Puzzle, PuzzleError};
+
+/// JSON description of a board: the size of its columns and their balls, bottom to top.
+#[derive(Serialize, Deserialize)]
+struct JsonPuzzle {
+    column_size: Capacity,
+    columns: Vec<Vec<Color>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    joker: Option<Color>,
+    #[serde(default, skip_serializing_if = "MoveMode::is_run")]
+    move_mode: MoveMode,
+}
+
+/// JSON description of a solution, along with the board it solves.
+#[derive(Serialize)]
+struct JsonSolution<'a> {
+    board: JsonPuzzle,
+    moves: &'a [Move],
+}
+
+impl Puzzle {
+    /// Reads a board from JSON shaped like `{ "column_size": 4, "columns": [[1, 2, 3], []] }`,
+    /// where `column_size` may also list the size of each column, an optional `joker` gives the
+    /// color of the jokers and an optional `move_mode` of `"single"` moves one ball at a time.
+    /// Fails if the input is not shaped like that, or with the same errors as
+    /// [`Puzzle::try_new`].
+    pub fn from_json(json: &str) -> Result<Puzzle, PuzzleError> {
+        let raw: JsonPuzzle =
+            serde_json::from_str(json).map_err(|e| PuzzleError::Json(e.to_string()))?;
+        let mut puzzle = Puzzle::try_new(raw.column_size, &raw.columns)?;
+        puzzle.set_joker(raw.joker);
+        puzzle.set_move_mode(raw.move_mode);
+        Ok(puzzle)
+    }
+
+    /// Writes the board as JSON in the shape read by [`Puzzle::from_json`].
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.to_raw_json()).expect("boards can always be serialized")
+    }
+
+    /// Writes `moves` as JSON for a front-end, along with the board they start from, in the
+    /// shape `{ "board": { "column_size": .., "columns": .. }, "moves": [{ "from": .., "to": ..,
+    /// "count": .. }] }`.
+    pub fn solution_to_json(&self, moves: &[Move]) -> String {
+        let raw = JsonSolution {
+            board: self.to_raw_json(),
+            moves,
+        };
+        serde_json::to_string(&raw).expect("solutions can always be serialized")
+    }
+
+    /// Saves a game in progress to `path`, as the indented JSON form of the [`Puzzle`], which
+    /// includes the moves played so far so that they can still be undone once loaded back.
+    pub fn save_game(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("boards can always be serialized");
+        fs::write(path, json + "\n")
+    }
+
+    /// Loads a game saved with [`Puzzle::save_game`]. Fails with an [`io::ErrorKind::InvalidData`]
+    /// error wrapping a [`PuzzleError`] if the file doesn't hold a valid board, or if its history
+    /// doesn't lead to that board.
+    pub fn load_game(path: impl AsRef<Path>) -> io::Result<Puzzle> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, PuzzleError::Json(e.to_string()))
+        })
+    }
+
+    fn to_raw_json(&self) -> JsonPuzzle {
+        JsonPuzzle {
+            column_size: self.capacity.clone(),
+            columns: self.state.iter().map(|col| col.to_vec()).collect(),
+            joker: self.joker,
+            move_mode: self.move_mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solutions_parse_back_to_their_moves() {
+        let p = crate::tests::main_board();
+        let moves = Vec::from(p.solve(5, 100));
+        let json: serde_json::Value = serde_json::from_str(&p.solution_to_json(&moves)).unwrap();
+        let parsed: Vec<Move> = serde_json::from_value(json["moves"].clone()).unwrap();
+        assert_eq!(parsed, moves);
+        let board = Puzzle::from_json(&json["board"].to_string()).unwrap();
+        assert_eq!(board.state, p.state);
+    }
+
+    #[test]
+    fn saved_games_load_back_with_their_history() {
+        let mut p = crate::tests::main_board();
+        p.do_move(crate::tests::mv(0, 6)).unwrap();
+        p.do_move(crate::tests::mv(1, 7)).unwrap();
+        let path = std::env::temp_dir().join(format!("sorting-save-{}.json", std::process::id()));
+        p.save_game(&path).unwrap();
+        let loaded = Puzzle::load_game(&path);
+        fs::remove_file(&path).unwrap();
+
+        let mut loaded = loaded.unwrap();
+        assert_eq!(loaded.state, p.state);
+        assert_eq!(loaded.history(), p.history());
+        while loaded.undo().is_some() {}
+        assert_eq!(loaded.state, crate::tests::main_board().state);
+    }
+}
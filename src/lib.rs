@@ -0,0 +1,2215 @@
+mod builder;
+mod code;
+mod color_map;
+mod difficulty;
+mod error;
+mod generate;
+mod invariants;
+#[cfg(feature = "serde")]
+mod json;
+mod matrix;
+mod parse;
+#[cfg(feature = "python")]
+mod python;
+mod rank;
+mod relabel;
+mod render;
+mod search;
+mod simplify;
+mod trace;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod zobrist;
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::collections::hash_map::Entry;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use log::info;
+use zobrist::ZobristHash;
+
+pub use builder::PuzzleBuilder;
+pub use color_map::{ColorMap, NamedDisplay};
+pub use difficulty::Difficulty;
+pub use error::{ParsePuzzleError, PuzzleError};
+pub use parse::parse_moves;
+pub use rank::{DefaultRank, Heuristic, RankWeights};
+pub use simplify::AnnotatedMove;
+pub use trace::SearchTrace;
+
+/// Identifier of the color of a ball, the default color type of [`Puzzle`].
+pub type Color = u32;
+/// Balls of a column, from bottom to top.
+#[cfg(not(feature = "smallvec"))]
+pub type Column<C = Color> = Vec<C>;
+/// Balls of a column, from bottom to top, stored inline up to 8 balls so that copying a board
+/// doesn't allocate for each of its columns.
+#[cfg(feature = "smallvec")]
+pub type Column<C = Color> = smallvec::SmallVec<[C; 8]>;
+
+/// Color types the searches can work with. On top of what the rules need, colors have to be
+/// ordered to sort the columns into a canonical form, and shared between threads by the parallel
+/// search. Every `Copy` type meeting those bounds qualifies, such as `u8` or a plain enum.
+pub trait SearchColor: Copy + Ord + Hash + Send + Sync {}
+
+impl<C: Copy + Ord + Hash + Send + Sync> SearchColor for C {}
+
+/// Number of balls the columns of a board can hold.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum Capacity {
+    /// All the columns have the same size.
+    Uniform(usize),
+    /// The size of each column, for variants with tubes of different heights.
+    PerColumn(Vec<usize>),
+}
+
+impl Capacity {
+    /// Returns the number of balls column `col` can hold.
+    pub fn of(&self, col: usize) -> usize {
+        match self {
+            Capacity::Uniform(size) => *size,
+            Capacity::PerColumn(sizes) => sizes[col],
+        }
+    }
+
+    /// Returns the size of the tallest column.
+    pub fn max(&self) -> usize {
+        match self {
+            Capacity::Uniform(size) => *size,
+            Capacity::PerColumn(sizes) => sizes.iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    /// Returns whether all the columns have the same size.
+    fn is_uniform(&self) -> bool {
+        match self {
+            Capacity::Uniform(_) => true,
+            Capacity::PerColumn(sizes) => sizes.windows(2).all(|w| w[0] == w[1]),
+        }
+    }
+}
+
+impl From<usize> for Capacity {
+    fn from(size: usize) -> Self {
+        Capacity::Uniform(size)
+    }
+}
+
+impl From<Vec<usize>> for Capacity {
+    fn from(sizes: Vec<usize>) -> Self {
+        Capacity::PerColumn(sizes)
+    }
+}
+
+impl From<&[usize]> for Capacity {
+    fn from(sizes: &[usize]) -> Self {
+        Capacity::PerColumn(sizes.to_vec())
+    }
+}
+
+/// How many balls a pour moves, set with [`Puzzle::set_move_mode`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MoveMode {
+    /// The whole run of balls of the same color at the top of the source, as far as there is
+    /// room for it.
+    #[default]
+    Run,
+    /// Only the top ball of the source, for variants moving one ball at a time.
+    Single,
+}
+
+#[cfg(feature = "serde")]
+impl MoveMode {
+    fn is_run(&self) -> bool {
+        *self == MoveMode::Run
+    }
+}
+
+/// Best score found for each board, keyed by canonical hash, along with the depth it was searched
+/// to.
+type TranspositionTable = HashMap<u64, (u32, Score)>;
+
+/// How the moves from the root of each search are split across threads.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+enum Split {
+    #[default]
+    Sequential,
+    /// Each thread spawned by [`Puzzle::solve_parallel`] takes the next move.
+    Threads,
+    /// The moves are searched on the rayon thread pool by [`Puzzle::solve_rayon`].
+    #[cfg(feature = "rayon")]
+    Rayon,
+}
+
+/// State shared by all the nodes of a depth-first search.
+struct SearchContext<'a, C = Color> {
+    table: Option<&'a mut TranspositionTable>,
+    /// Interrupts the search when it returns true, making its result meaningless.
+    stop: Option<&'a (dyn Fn() -> bool + Sync)>,
+    split: Split,
+    /// Evaluation of the boards, [`Puzzle::rank`] if not set.
+    heuristic: Option<&'a dyn Heuristic<C>>,
+    /// Interrupts the search once passed, like `stop`.
+    deadline: Option<Instant>,
+    /// Interrupts the search once set, like `stop`.
+    cancel: Option<&'a AtomicBool>,
+    progress: Option<Progress<'a>>,
+    /// Receives the moves as soon as they are committed.
+    stream: Option<&'a Sender<Move>>,
+    /// Records the boards and moves explored.
+    trace: Option<&'a mut SearchTrace>,
+    /// Number of nodes visited so far.
+    nodes: u64,
+    /// Number of boards skipped thanks to the transposition table.
+    table_hits: u64,
+    /// Largest number of moves looked ahead so far.
+    max_depth_reached: u32,
+    /// Number of lines committed so far.
+    iterations: u32,
+    /// Set once the search has been interrupted by the deadline, `cancel` or the bounds of
+    /// `config`.
+    halted: bool,
+    /// Bounds interrupting the search, like `deadline` but deterministic, and other options.
+    config: SolveConfig,
+}
+
+// Not derived, as that would require the color type to implement `Default`.
+impl<C> Default for SearchContext<'_, C> {
+    fn default() -> Self {
+        SearchContext {
+            table: None,
+            stop: None,
+            split: Split::Sequential,
+            heuristic: None,
+            deadline: None,
+            cancel: None,
+            progress: None,
+            stream: None,
+            trace: None,
+            nodes: 0,
+            table_hits: 0,
+            max_depth_reached: 0,
+            iterations: 0,
+            halted: false,
+            config: SolveConfig::default(),
+        }
+    }
+}
+
+/// Board being searched by `dfs`, which is the board reached by the pending moves of the nodes
+/// below it on the stack.
+struct DfsNode {
+    depth: u32,
+    /// Moves left to search, the next one last.
+    moves: Vec<Move>,
+    /// Move whose line is being searched, with the number of balls it poured and the hash of the
+    /// board it leads to if it has to be stored in the table.
+    pending: Option<(Move, usize, Option<u64>)>,
+    best_score: Score,
+    best_moves: VecDeque<Move>,
+}
+
+/// Callback told about the progress of a search, with the last state reported to it.
+struct Progress<'a> {
+    callback: &'a mut dyn FnMut(SolveProgress),
+    report: SolveProgress,
+}
+
+impl<C: Copy + Eq + Hash> SearchContext<'_, C> {
+    /// Number of nodes between two checks of the clock and of `cancel`.
+    const CHECK_INTERVAL: u64 = 128;
+    /// Number of nodes between two calls of the progress callback.
+    const PROGRESS_INTERVAL: u64 = 4096;
+
+    /// Counts a visited node and returns whether the search has to be interrupted.
+    fn interrupted(&mut self) -> bool {
+        self.nodes += 1;
+        if !self.halted && self.nodes.is_multiple_of(Self::CHECK_INTERVAL) {
+            let timed_out = self.deadline.is_some_and(|d| Instant::now() >= d);
+            let cancelled = self.cancel.is_some_and(|c| c.load(Ordering::Relaxed));
+            self.halted = timed_out || cancelled;
+        }
+        let too_many_nodes = self.config.max_nodes.is_some_and(|max| self.nodes > max);
+        let too_many_states = self
+            .config
+            .max_states
+            .is_some_and(|max| self.table.as_ref().is_some_and(|t| t.len() > max));
+        self.halted |= too_many_nodes || too_many_states;
+        if let Some(progress) = &mut self.progress
+            && self.nodes.is_multiple_of(Self::PROGRESS_INTERVAL)
+        {
+            progress.report.nodes_visited = self.nodes;
+            (progress.callback)(progress.report);
+        }
+        self.halted || self.stop.is_some_and(|stop| stop())
+    }
+
+    /// Evaluates `p` with the heuristic of the search.
+    fn score(&self, p: &Puzzle<C>) -> Score {
+        match self.heuristic {
+            Some(heuristic) => heuristic.score(p),
+            None => p.rank(),
+        }
+    }
+
+    /// Sends freshly committed moves to the stream, if any. The moves are returned by the
+    /// search anyway, so a receiver that hung up is not an error.
+    fn send_moves(&self, moves: &VecDeque<Move>) {
+        if let Some(tx) = self.stream {
+            for &m in moves {
+                let _ = tx.send(m);
+            }
+        }
+    }
+
+    /// Records in the table, if any, that the board of `hash` scores `score` when searched
+    /// `depth` moves deep, unless it was already searched deeper.
+    fn store(&mut self, hash: Option<u64>, depth: u32, score: Score) {
+        if let (Some(table), Some(hash)) = (self.table.as_deref_mut(), hash) {
+            let entry = table.entry(hash).or_insert((depth, score));
+            if entry.0 < depth {
+                *entry = (depth, score);
+            }
+        }
+    }
+
+    /// Records the state of the search on `game`, looking `depth` moves ahead after committing
+    /// `committed` moves, for the next progress report.
+    fn set_progress(&mut self, game: &Puzzle<C>, depth: u32, committed: usize) {
+        if self.progress.is_none() {
+            return;
+        }
+        let best_score = self.score(game);
+        if let Some(progress) = &mut self.progress {
+            progress.report = SolveProgress {
+                nodes_visited: self.nodes,
+                best_score,
+                depth,
+                best_len: committed,
+            };
+        }
+    }
+}
+
+impl<C: SearchColor> SearchContext<'_, C> {
+    /// Returns the hash and score of `game` if the search is traced, to record the moves played
+    /// from it.
+    fn trace_parent(&self, game: &Puzzle<C>) -> Option<(u64, Score)> {
+        self.trace
+            .is_some()
+            .then(|| (game.canonical_hash(), self.score(game)))
+    }
+
+    /// Records in the trace, if any, that `m` led from the board of `parent` to `game`.
+    fn trace_move(&mut self, parent: Option<(u64, Score)>, game: &Puzzle<C>, m: Move) {
+        if let Some(parent) = parent {
+            let child = (game.canonical_hash(), self.score(game));
+            if let Some(trace) = self.trace.as_deref_mut() {
+                trace.record(parent, child, m);
+            }
+        }
+    }
+}
+
+/// Options of [`Puzzle::solve_with_config`]. Unlike a time budget, the bounds on the work of
+/// the search give the same result on every run.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SolveConfig {
+    /// Number of nodes after which the search gives up.
+    pub max_nodes: Option<u64>,
+    /// Number of boards the transposition table may hold before the search gives up, which
+    /// bounds the memory it uses.
+    pub max_states: Option<usize>,
+    /// Whether the moves of each node are searched best first, according to the score of the
+    /// board they lead to. Wins are found sooner and the transposition table prunes more, at the
+    /// cost of evaluating every child before searching it.
+    pub order_moves: bool,
+    /// Whether nodes with a move from [`Puzzle::forced_moves`] only search that move.
+    pub apply_forced: bool,
+}
+
+/// Counters of a search, returned by [`Puzzle::solve_with_stats`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    pub nodes_visited: u64,
+    /// Largest number of moves the search looked ahead.
+    pub max_depth_reached: u32,
+    /// Number of lines committed.
+    pub iterations_used: u32,
+    pub elapsed: Duration,
+    /// Number of boards skipped because the transposition table proved them no better.
+    pub transpositions_hit: u64,
+}
+
+/// State of a search, periodically reported by [`Puzzle::solve_with_progress`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SolveProgress {
+    pub nodes_visited: u64,
+    /// Score of the board reached by the moves committed so far.
+    pub best_score: Score,
+    /// Number of moves the search is currently looking ahead.
+    pub depth: u32,
+    /// Number of moves committed so far.
+    pub best_len: usize,
+}
+
+/// Evaluation of a board, ordered so that better boards compare greater.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Score {
+    /// The board is deadlocked, see [`Puzzle::is_deadlocked`].
+    Deadlocked,
+    Score(usize),
+    Win,
+}
+
+/// A ball sort puzzle: columns of colored balls that must be sorted so that each color ends up
+/// in a single column.
+///
+/// Colors are [`Color`] numbers by default, which is what parsing, printing and generating
+/// boards work with. The rules and the searches work with any color type, e.g. `u8` to save
+/// memory or an enum for type safety.
+///
+/// One of the colors may be set as a joker with [`Puzzle::set_joker`]: jokers can be poured
+/// onto any ball and any ball onto them, and they don't need to be sorted.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Puzzle<C = Color> {
+    capacity: Capacity,
+    /// Number of balls of each color, jokers aside.
+    colors_count: HashMap<C, usize>,
+    state: Vec<Column<C>>,
+    /// Hash of the board up to the order of its columns, updated by each move.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    zobrist: ZobristHash,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    joker: Option<C>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "MoveMode::is_run"))]
+    move_mode: MoveMode,
+    /// Moves played with `do_move`, with the number of balls they actually poured.
+    history: Vec<Move>,
+    /// Whether no move is generated out of complete columns.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    lock_complete: bool,
+}
+
+/// [`Puzzle`] with the default color type.
+pub type Puzzle32 = Puzzle<u32>;
+
+/// Unvalidated form of a deserialized [`Puzzle`]. `colors_count` is derived from `state`, so it
+/// may be omitted, but when present it has to match what the state actually contains. The
+/// `history` may be omitted too, but when present it has to lead to `state`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerdePuzzle {
+    #[serde(alias = "column_size")]
+    capacity: Capacity,
+    #[serde(default)]
+    colors_count: Option<HashMap<Color, usize>>,
+    state: Vec<Vec<Color>>,
+    #[serde(default)]
+    history: Vec<Move>,
+    #[serde(default)]
+    joker: Option<Color>,
+    #[serde(default)]
+    move_mode: MoveMode,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerdePuzzle> for Puzzle {
+    type Error = PuzzleError;
+
+    fn try_from(raw: SerdePuzzle) -> Result<Self, Self::Error> {
+        let mut puzzle = Puzzle::try_new(raw.capacity, &raw.state)?;
+        puzzle.set_joker(raw.joker);
+        puzzle.set_move_mode(raw.move_mode);
+        if let Some(colors_count) = raw.colors_count {
+            invariants::check_counts(&colors_count, &puzzle.colors_count)?;
+        }
+        puzzle.restore_history(raw.history)?;
+        Ok(puzzle)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Puzzle {
+    /// Sets the history of a deserialized board, after checking that taking its moves back from
+    /// the last one, then playing them again, gets back to this board with the same counts.
+    fn restore_history(&mut self, history: Vec<Move>) -> Result<(), PuzzleError> {
+        let mut start = self.without_history();
+        for (index, &m) in history.iter().enumerate().rev() {
+            let columns = start.state.len();
+            let undoable = m.from < columns
+                && m.to < columns
+                && m.from != m.to
+                && m.count > 0
+                && start.top_run_length(m.to) >= m.count
+                && start.free_space(m.from) >= m.count;
+            if !undoable {
+                return Err(PuzzleError::InvalidHistory { index });
+            }
+            start.undo_move(m, m.count);
+        }
+        for (index, &m) in history.iter().enumerate() {
+            if start.do_move(m) != Ok(m.count) {
+                return Err(PuzzleError::InvalidHistory { index });
+            }
+        }
+        self.history = history;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Puzzle {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = SerdePuzzle::deserialize(deserializer)?;
+        Puzzle::try_from(raw).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns the token used to print a color: digits for 0-9, `A`-`Z` for 10-35 and lowercase hex
+/// (at least two characters) beyond that.
+fn color_token(c: Color) -> String {
+    match char::from_digit(c, 36) {
+        Some(d) => d.to_ascii_uppercase().to_string(),
+        None => format!("{c:02x}"),
+    }
+}
+
+/// Counts the balls of each color on a board, jokers aside. This is the only place
+/// `colors_count` is computed.
+fn recount<C: Copy + Eq + Hash>(state: &[Column<C>], joker: Option<C>) -> HashMap<C, usize> {
+    let mut counts = HashMap::new();
+    for &c in state.iter().flatten().filter(|&&c| Some(c) != joker) {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    counts
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_grid(f, color_token)
+    }
+}
+
+/// Boards are equal when they have the same canonical form, so that boards only differing by
+/// the order of their columns compare equal. The history and the locking of complete columns
+/// are ignored.
+impl<C: SearchColor> PartialEq for Puzzle<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.joker == other.joker
+            && self.move_mode == other.move_mode
+            && self.canonical_capacity() == other.canonical_capacity()
+            && self.canonical() == other.canonical()
+    }
+}
+
+impl<C: SearchColor> Eq for Puzzle<C> {}
+
+/// Hashes the canonical form, consistently with `PartialEq`.
+impl<C: SearchColor> Hash for Puzzle<C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical_hash().hash(state);
+    }
+}
+
+impl Puzzle {
+    /// Writes the board as a grid of `[c]` cells, top row first, with `token` giving the text of
+    /// each color.
+    fn fmt_grid(&self, f: &mut fmt::Formatter<'_>, token: impl Fn(Color) -> String) -> fmt::Result {
+        // Every cell is padded to the widest token so that columns stay aligned.
+        let width = self
+            .colors_count
+            .keys()
+            .chain(&self.joker)
+            .map(|&c| token(c).chars().count())
+            .max()
+            .unwrap_or(1);
+        let height = self.capacity.max();
+        for i in 0..height {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for j in 0..self.state.len() {
+                let col = &self.state[j];
+                if j > 0 {
+                    write!(f, " ")?;
+                }
+                let idx = height - i - 1;
+                if idx >= self.capacity.of(j) {
+                    // Above the top of a shorter column.
+                    write!(f, "{:1$}", "", width + 2)?;
+                    continue;
+                }
+                let c = col.get(idx).map(|&x| token(x)).unwrap_or_default();
+                write!(f, "[{c:>width$}]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A pour of the top balls of a column onto another one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    /// Number of balls poured, given the top run of the source and the free space of the
+    /// destination.
+    pub count: usize,
+}
+
+impl Move {
+    /// Returns the pour taking back this move once it poured `count` balls, which is why
+    /// [`Puzzle::do_move`] returns that number: the same balls poured the other way.
+    ///
+    /// Pouring those balls back always restores the board, which is what [`Puzzle::undo_move`]
+    /// does whatever their colors. `do_move` follows the rules instead, so it only accepts the
+    /// inverse when the balls may land back, e.g. when the move emptied its source: the ball a
+    /// move uncovers usually has another color.
+    pub fn inverse(self, count: usize) -> Move {
+        Move {
+            from: self.to,
+            to: self.from,
+            count,
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    /// Writes the move as `from -> to`, followed by the number of balls like `(×2)` unless it
+    /// is unknown, which is the notation read back by [`parse_moves`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}", self.from, self.to)?;
+        if self.count > 0 {
+            write!(f, " (×{})", self.count)?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Copy + Eq + Hash> Puzzle<C> {
+    /// Same as [`Puzzle::try_new`], but panics if the input is invalid.
+    pub fn new(capacity: impl Into<Capacity>, init: &[Vec<C>]) -> Self {
+        Self::try_new(capacity, init).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Builds a puzzle from its columns, listed bottom to top. `capacity` is either the size of
+    /// all the columns or a vector with the size of each one. Fails if any column holds more
+    /// balls than it can, or if the number of sizes doesn't match the number of columns.
+    pub fn try_new(capacity: impl Into<Capacity>, init: &[Vec<C>]) -> Result<Self, PuzzleError> {
+        let capacity = capacity.into();
+        if let Capacity::PerColumn(sizes) = &capacity
+            && sizes.len() != init.len()
+        {
+            return Err(PuzzleError::CapacityCount {
+                columns: init.len(),
+                capacities: sizes.len(),
+            });
+        }
+        let mut state = Vec::new();
+        // No column can ever hold more than all the balls, whatever its size, which may come
+        // from untrusted input.
+        let balls: usize = init.iter().map(Vec::len).sum();
+
+        for (i, col) in init.iter().enumerate() {
+            let size = capacity.of(i);
+            if col.len() > size {
+                return Err(PuzzleError::Overfilled {
+                    column: i,
+                    len: col.len(),
+                    capacity: size,
+                });
+            }
+            let mut vec = Column::with_capacity(size.min(balls));
+            vec.extend(col.iter().copied());
+            state.push(vec);
+        }
+
+        Ok(Puzzle {
+            zobrist: ZobristHash::new(&state, &capacity),
+            capacity,
+            colors_count: recount(&state, None),
+            state,
+            joker: None,
+            move_mode: MoveMode::Run,
+            history: Vec::new(),
+            lock_complete: true,
+        })
+    }
+
+    /// Checks that `colors_count` still matches the balls on the board. Moves never change how
+    /// many balls of each color there are, so this only fails if a mutator is broken; it is
+    /// called after each of them in debug builds and does nothing in release builds.
+    fn debug_check_counts(&self) {
+        debug_assert!(
+            self.colors_count == recount(&self.state, self.joker),
+            "colors_count no longer matches the board"
+        );
+    }
+
+    /// Sets the color of the jokers, `None` for a board without any. Jokers can be poured onto
+    /// any ball and any ball onto them, a run of balls of one color keeping jokers mixed in, and
+    /// they may end up anywhere on a solved board: each column has to hold all the balls of a
+    /// single color besides its jokers, or only jokers.
+    pub fn set_joker(&mut self, joker: Option<C>) {
+        self.joker = joker;
+        self.colors_count = recount(&self.state, joker);
+    }
+
+    /// Returns the color of the jokers, if any.
+    pub fn joker(&self) -> Option<C> {
+        self.joker
+    }
+
+    /// Sets how many balls a pour moves: the whole top run by default, or a single ball.
+    pub fn set_move_mode(&mut self, mode: MoveMode) {
+        self.move_mode = mode;
+    }
+
+    /// Returns how many balls a pour moves.
+    pub fn move_mode(&self) -> MoveMode {
+        self.move_mode
+    }
+
+    /// Returns whether `c` is the color of the jokers.
+    #[inline]
+    fn is_joker(&self, c: C) -> bool {
+        self.joker == Some(c)
+    }
+
+    /// Returns the color of the balls of `col` if they are all of that color, jokers aside,
+    /// `None` if the column is empty, only holds jokers or mixes colors.
+    fn single_color(&self, col: &[C]) -> Option<C> {
+        let mut balls = col.iter().copied().filter(|&c| !self.is_joker(c));
+        let c = balls.next()?;
+        balls.all(|c2| c2 == c).then_some(c)
+    }
+
+    /// Returns the number of columns of the board.
+    pub fn num_columns(&self) -> usize {
+        self.state.len()
+    }
+
+    /// Returns the number of distinct colors on the board.
+    pub fn num_colors(&self) -> usize {
+        self.colors_count.len()
+    }
+
+    /// Returns the number of balls a column can hold, or the size of the tallest column with
+    /// per-column capacities.
+    pub fn column_size(&self) -> usize {
+        self.capacity.max()
+    }
+
+    /// Returns the number of balls each column can hold.
+    pub fn capacity(&self) -> &Capacity {
+        &self.capacity
+    }
+
+    /// Returns the balls of column `i`, from bottom to top. Panics if there is no such column.
+    pub fn column(&self, i: usize) -> &[C] {
+        &self.state[i]
+    }
+
+    /// Returns whether every non-empty column is made of a single color and holds all the balls
+    /// of that color, which for a regular board means it is full. Jokers may be mixed in with
+    /// any color, or fill columns of their own.
+    pub fn is_solved(&self) -> bool {
+        (0..self.state.len())
+            .all(|i| self.is_complete(i) || self.state[i].iter().all(|&c| self.is_joker(c)))
+    }
+
+    /// Returns the number of columns holding all the balls of a single color, jokers aside.
+    pub fn completed_columns(&self) -> usize {
+        (0..self.state.len())
+            .filter(|&i| self.is_complete(i))
+            .count()
+    }
+
+    /// Returns the number of colors whose balls are not all gathered in a single column yet.
+    pub fn remaining_colors(&self) -> usize {
+        // A complete column holds every ball of its color, so no two share a color.
+        self.num_colors() - self.completed_columns()
+    }
+
+    /// Scores the board heuristically, returning [`Score::Win`] once it is solved. This is
+    /// the evaluation of [`DefaultRank`].
+    pub fn rank(&self) -> Score {
+        DefaultRank.score(self)
+    }
+
+    /// Sets whether complete columns, holding all the balls of their color, are locked. When the
+    /// columns all have the same size, moves out of them are never useful, so by default they
+    /// are not generated by [`Puzzle::column_moves`] and the searches don't waste time on them.
+    /// With columns of different sizes, a complete column may have to move to free a column
+    /// another color needs, so no column is locked then.
+    pub fn set_lock_complete(&mut self, lock: bool) {
+        self.lock_complete = lock;
+    }
+
+    /// Returns the moves pouring from column `col`, none if it is a locked complete column.
+    pub fn column_moves(&self, col: usize) -> impl Iterator<Item = Move> {
+        let src = &self.state[col];
+        let iter;
+
+        if self.is_locked(col) {
+            iter = None;
+        } else if let Some(&c) = src.last() {
+            let run = self.movable_run(col);
+            // Pouring into any of the empty columns of a given size gives the same board up to
+            // the order of the columns, so only the first one is kept.
+            let mut empty_sizes = Vec::new();
+            iter = Some(
+                self.state
+                    .iter()
+                    .enumerate()
+                    .filter(move |(i, _)| *i != col)
+                    .filter(move |&(i, dst)| {
+                        dst.last().is_none_or(|&c2| c2 == c)
+                            || self.joker.is_some() && self.pour_size(col, i) > 0
+                    })
+                    .filter(|&(i, _)| !self.is_full(i))
+                    .filter(move |&(i, dst)| {
+                        let size = self.capacity.of(i);
+                        if !dst.is_empty() {
+                            true
+                        } else if empty_sizes.contains(&size) {
+                            false
+                        } else {
+                            empty_sizes.push(size);
+                            true
+                        }
+                    })
+                    .map(move |(i, _)| Move {
+                        from: col,
+                        to: i,
+                        count: match self.joker {
+                            Some(_) => self.pour_size(col, i),
+                            None => run.min(self.free_space(i)),
+                        },
+                    }),
+            );
+        } else {
+            iter = None;
+        }
+
+        iter.into_iter().flatten()
+    }
+
+    /// Returns the number of balls that can still be poured into column `col`.
+    #[inline]
+    pub fn free_space(&self, col: usize) -> usize {
+        self.capacity.of(col) - self.state[col].len()
+    }
+
+    /// Returns whether column `col` has no free slot left.
+    #[inline]
+    pub fn is_full(&self, col: usize) -> bool {
+        self.free_space(col) == 0
+    }
+
+    /// Returns the number of balls of the same color at the top of column `col`, along with
+    /// the jokers among them, 0 if it is empty.
+    pub fn top_run_length(&self, col: usize) -> usize {
+        let col = &self.state[col];
+        if self.joker.is_none() {
+            return match col.last() {
+                Some(&c) => col.iter().rev().take_while(|&&c2| c2 == c).count(),
+                None => 0,
+            };
+        }
+        // Color of the run, set by its first ball that is not a joker.
+        let mut color = None;
+        col.iter()
+            .rev()
+            .take_while(|&&c| self.is_joker(c) || *color.get_or_insert(c) == c)
+            .count()
+    }
+
+    /// Returns the number of balls at the top of column `col` a pour may move: its top run, or
+    /// only its top ball with [`MoveMode::Single`].
+    fn movable_run(&self, col: usize) -> usize {
+        let run = self.top_run_length(col);
+        match self.move_mode {
+            MoveMode::Run => run,
+            MoveMode::Single => run.min(1),
+        }
+    }
+
+    /// Returns the number of balls a pour from `from` to `to` moves: as many balls of the
+    /// movable run of `from` as fit in `to`, as long as the lowest of them matches the top of
+    /// `to`, or else fewer. Returns 0 if no ball can be poured, e.g. if their colors differ. The
+    /// columns are assumed to exist and to differ.
+    fn pour_size(&self, from: usize, to: usize) -> usize {
+        let (src, dst) = (&self.state[from], &self.state[to]);
+        let max = self.movable_run(from).min(self.free_space(to));
+        let Some(&top) = dst.last() else {
+            return max;
+        };
+        if self.joker.is_none() {
+            return if src.last() == Some(&top) { max } else { 0 };
+        }
+        (1..=max)
+            .rev()
+            .find(|&n| {
+                let c = src[src.len() - n];
+                c == top || self.is_joker(c) || self.is_joker(top)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of buried balls, which have a ball of another color somewhere above
+    /// them in their column and so can't be sorted before it is moved away.
+    pub fn buried_count(&self) -> usize {
+        (0..self.state.len())
+            .map(|i| self.state[i].len() - self.top_run_length(i))
+            .sum()
+    }
+
+    /// Returns the number of balls `m` would pour, which is the length of the top run of its
+    /// source capped by the free slots of its destination, or 0 if the move is not legal.
+    pub fn pour_count(&self, m: Move) -> usize {
+        if !self.can_move(m) {
+            return 0;
+        }
+        self.pour_size(m.from, m.to)
+    }
+
+    /// Returns every move available on the board. This is the raw generator used by the solvers,
+    /// which includes pointless moves such as breaking up a complete column when they are not
+    /// locked, but only pours into the first empty column of each size; see
+    /// [`Puzzle::legal_moves`] for the moves worth offering to a player.
+    pub fn moves(&self) -> impl Iterator<Item = Move> {
+        self.state
+            .iter()
+            .enumerate()
+            .flat_map(|(i, _)| self.column_moves(i))
+    }
+
+    /// Returns the moves that actually change the board: they pour at least one ball into
+    /// another column, and never from a locked complete column, see
+    /// [`Puzzle::set_lock_complete`]. Unlike [`Puzzle::moves`], pours into each of the empty
+    /// columns are listed.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let n = self.state.len();
+        (0..n)
+            .filter(|&from| !self.is_locked(from))
+            .flat_map(|from| (0..n).map(move |to| Move { from, to, count: 0 }))
+            .filter(|&m| self.can_move(m))
+            .map(|m| Move {
+                count: self.pour_count(m),
+                ..m
+            })
+            .collect()
+    }
+
+    /// Returns whether no move may pour out of column `col`, see [`Puzzle::set_lock_complete`].
+    fn is_locked(&self, col: usize) -> bool {
+        self.lock_complete && self.capacity.is_uniform() && self.is_complete(col)
+    }
+
+    /// Returns the moves that are always safe to play: the ones completing a color by pouring its
+    /// last balls onto a column holding all the others and nothing else, filling it up. That
+    /// color is then sorted for good, and with columns all of the same size, no other column
+    /// could have held it any better, so no solution is lost. With per-column capacities,
+    /// jokers or single-ball moves this can't be proven, so no move is returned.
+    pub fn forced_moves(&self) -> Vec<Move> {
+        if !matches!(self.capacity, Capacity::Uniform(_))
+            || self.joker.is_some()
+            || self.move_mode == MoveMode::Single
+        {
+            return Vec::new();
+        }
+        (0..self.state.len())
+            .flat_map(|from| (0..self.state.len()).map(move |to| (from, to)))
+            .filter_map(|(from, to)| {
+                let run = self.top_run_length(from);
+                let dst = &self.state[to];
+                let &c = dst.last()?;
+                let completes = from != to
+                    && self.state[from].last() == Some(&c)
+                    && dst.iter().all(|&c2| c2 == c)
+                    && dst.len() + run == self.colors_count[&c]
+                    && self.free_space(to) == run;
+                completes.then_some(Move {
+                    from,
+                    to,
+                    count: run,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns whether column `col` holds all the balls of a single color, and possibly jokers.
+    fn is_complete(&self, col: usize) -> bool {
+        let col = &self.state[col];
+        if self.joker.is_none() {
+            return col.last().is_some_and(|&c| {
+                col.len() == self.colors_count[&c] && col.iter().all(|&c2| c2 == c)
+            });
+        }
+        self.single_color(col)
+            .is_some_and(|c| col.iter().filter(|&&c2| c2 == c).count() == self.colors_count[&c])
+    }
+
+    /// Pours the top run of balls of the source column onto the destination, as long as there
+    /// is room, or only its top ball with [`MoveMode::Single`], and returns the number of balls
+    /// moved. The `count` of the move is not checked, as many balls as the rules allow are
+    /// always poured. Fails without touching the board if the move is not legal.
+    pub fn do_move(&mut self, m: Move) -> Result<usize, PuzzleError> {
+        self.check_move(m)?;
+        let count = self.pour(m);
+        self.history.push(Move { count, ..m });
+        Ok(count)
+    }
+
+    /// Returns whether `do_move` would accept `m`: both columns exist and differ, the source is
+    /// not empty, and the destination has a free slot and is empty or topped with the same color,
+    /// jokers matching any color.
+    pub fn can_move(&self, m: Move) -> bool {
+        self.check_move(m).is_ok()
+    }
+
+    /// Checks the rules of a pour, returning the reason why `m` cannot be played.
+    fn check_move(&self, Move { from, to, .. }: Move) -> Result<(), PuzzleError> {
+        for i in [from, to] {
+            if i >= self.state.len() {
+                return Err(PuzzleError::NoSuchColumn(i));
+            }
+        }
+        if from == to {
+            return Err(PuzzleError::SameColumn(from));
+        }
+        if self.state[from].is_empty() {
+            return Err(PuzzleError::EmptyColumn(from));
+        }
+        if self.is_full(to) {
+            return Err(PuzzleError::ColumnFull(to));
+        }
+        if self.pour_size(from, to) == 0 {
+            return Err(PuzzleError::ColorMismatch { from, to });
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move played with `do_move` and returns it, or `None` if there is no
+    /// move left to undo.
+    pub fn undo(&mut self) -> Option<Move> {
+        let m = self.history.pop()?;
+        self.undo_move(m, m.count);
+        Some(m)
+    }
+
+    /// Returns the moves played with `do_move` that haven't been undone, oldest first.
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Returns a copy of the board with `m` played as by [`Puzzle::do_move`], leaving this one
+    /// untouched. Fails the same way if the move is not legal.
+    pub fn with_move(&self, m: Move) -> Result<Puzzle<C>, PuzzleError> {
+        self.check_move(m)?;
+        let mut next = self.clone();
+        let count = next.pour(m);
+        next.history.push(Move { count, ..m });
+        Ok(next)
+    }
+
+    /// Plays `moves` in order with [`Puzzle::do_move`]. Fails with [`PuzzleError::IllegalStep`]
+    /// on the first move that cannot be played, the moves before it staying played.
+    pub fn apply_moves(&mut self, moves: &[Move]) -> Result<(), PuzzleError> {
+        for (index, &m) in moves.iter().enumerate() {
+            self.do_move(m).map_err(|error| PuzzleError::IllegalStep {
+                index,
+                error: Box::new(error),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns the board before the first of `moves` and after each of them, e.g. to show a
+    /// solution step by step. The boards stop before the first move that cannot be played.
+    pub fn states_along(&self, moves: &[Move]) -> impl Iterator<Item = Puzzle<C>> {
+        let mut board = self.clone();
+        let after = moves.iter().map_while(move |&m| {
+            board.do_move(m).ok()?;
+            Some(board.clone())
+        });
+        std::iter::once(self.clone()).chain(after)
+    }
+
+    /// Checks that `moves` is a winning solution by replaying it on a copy of the board. Fails
+    /// with [`PuzzleError::IllegalStep`] on the first move that cannot be played, or with
+    /// [`PuzzleError::NotSolved`] if the board isn't solved once they all are.
+    pub fn verify_solution(&self, moves: &[Move]) -> Result<(), PuzzleError> {
+        let mut p = self.without_history();
+        p.apply_moves(moves)?;
+        if !p.is_solved() {
+            return Err(PuzzleError::NotSolved { moves: moves.len() });
+        }
+        Ok(())
+    }
+
+    /// Copy of the board without its history, for searches that play moves on their own.
+    fn without_history(&self) -> Puzzle<C> {
+        Puzzle {
+            capacity: self.capacity.clone(),
+            colors_count: self.colors_count.clone(),
+            state: self.state.clone(),
+            joker: self.joker,
+            move_mode: self.move_mode,
+            history: Vec::new(),
+            zobrist: self.zobrist.clone(),
+            lock_complete: self.lock_complete,
+        }
+    }
+
+    /// Same as `do_move`, for moves already known to be legal.
+    fn pour(&mut self, Move { from, to, .. }: Move) -> usize {
+        assert!(
+            !self.state[from].is_empty(),
+            "cannot move from an empty column"
+        );
+
+        let count = self.pour_size(from, to);
+        let lens = (self.state[from].len(), self.state[to].len());
+        for _ in 0..count {
+            let c = self.state[from].pop().expect("the run was just measured");
+            self.state[to].push(c);
+        }
+        self.zobrist
+            .pour(&self.capacity, (from, to), lens, &self.state[to][lens.1..]);
+        self.debug_check_counts();
+        count
+    }
+
+    /// Puts back `count` balls from the destination of `m` onto its source, undoing a move that
+    /// poured that many balls. Panics if the destination holds fewer balls than that. Unlike
+    /// [`Puzzle::undo`], this leaves the history untouched.
+    pub fn undo_move(&mut self, Move { from, to, .. }: Move, count: usize) {
+        let lens = (self.state[to].len(), self.state[from].len());
+        for _ in 0..count {
+            let c = self.state[to]
+                .pop()
+                .expect("cannot undo more balls than were poured");
+            self.state[from].push(c);
+        }
+        self.zobrist.pour(
+            &self.capacity,
+            (to, from),
+            lens,
+            &self.state[from][lens.1..],
+        );
+        self.debug_check_counts();
+    }
+}
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Returns the distinct colors on the board, in increasing order.
+    pub fn colors(&self) -> impl Iterator<Item = C> {
+        let mut colors: Vec<C> = self.colors_count.keys().copied().collect();
+        colors.sort_unstable();
+        colors.into_iter()
+    }
+
+    /// Returns the columns in lexicographic order, empty ones first. Boards that only differ by
+    /// the order of their columns, such as the same board with two identical or empty columns
+    /// swapped, are equivalent for solving and share the same canonical form, which is what the
+    /// searches use to avoid exploring them twice.
+    ///
+    /// With per-column capacities, columns are first ordered by size, since only columns of the
+    /// same size can be swapped.
+    pub fn canonical(&self) -> Vec<Column<C>> {
+        match &self.capacity {
+            Capacity::Uniform(_) => {
+                let mut columns = self.state.clone();
+                columns.sort_unstable();
+                columns
+            }
+            Capacity::PerColumn(sizes) => {
+                let mut columns: Vec<_> = sizes.iter().zip(&self.state).collect();
+                columns.sort_unstable();
+                columns.into_iter().map(|(_, col)| col.clone()).collect()
+            }
+        }
+    }
+
+    /// Returns the sizes of the columns in the order of [`Puzzle::canonical`].
+    fn canonical_capacity(&self) -> Capacity {
+        match &self.capacity {
+            Capacity::Uniform(size) => Capacity::Uniform(*size),
+            Capacity::PerColumn(sizes) => {
+                let mut sizes = sizes.clone();
+                sizes.sort_unstable();
+                Capacity::PerColumn(sizes)
+            }
+        }
+    }
+
+    /// Hash of the canonical form of the board, maintained by the moves rather than computed.
+    fn canonical_hash(&self) -> u64 {
+        self.zobrist.value()
+    }
+
+    /// Returns each move of [`Puzzle::legal_moves`] with the board it leads to, as played by
+    /// [`Puzzle::do_move`]. Moves leading back to the same board up to the order of the columns,
+    /// like pouring a whole column into an empty one, are left out.
+    pub fn neighbors(&self) -> impl Iterator<Item = (Move, Puzzle<C>)> {
+        self.legal_moves().into_iter().filter_map(|m| {
+            let next = self.with_move(m).expect("legal moves can be played");
+            (next != *self).then_some((m, next))
+        })
+    }
+
+    /// Returns whether the board is stuck: it is not solved and no move changes it, either
+    /// because there is no legal move or because they all lead back to the same board, like
+    /// pouring a whole column into an empty one.
+    pub fn is_deadlocked(&self) -> bool {
+        !self.is_solved() && self.neighbors().next().is_none()
+    }
+
+    /// Returns the hash of the canonical form of the board, the key the searches use to tell
+    /// the boards apart, so boards that only differ by the order of their columns hash the same.
+    /// The hash doesn't depend on the run, but may change with the version of the crate or of
+    /// the compiler. Each move updates it in time proportional to the balls it pours, so getting
+    /// it costs nothing.
+    pub fn state_hash(&self) -> u64 {
+        self.canonical_hash()
+    }
+
+    /// Searches the lines of up to `depth` moves and returns the best one along with its score,
+    /// `score` being the score of the board itself. The nodes are kept on an explicit stack
+    /// rather than the call stack, so that deep searches can't overflow it.
+    fn dfs(
+        &mut self,
+        depth: u32,
+        score: Score,
+        ctx: &mut SearchContext<C>,
+    ) -> (Score, VecDeque<Move>) {
+        let mut stack = match self.dfs_node(depth, score, ctx) {
+            Ok(node) => vec![node],
+            Err(leaf) => return leaf,
+        };
+        // Result of the child that was just searched, `None` if the table skipped it.
+        let mut returned: Option<Option<(Score, VecDeque<Move>)>> = None;
+        loop {
+            let node = stack.last_mut().expect("the root is popped last");
+            if let Some(child) = returned.take() {
+                let (m, count, _) = node.pending.take().expect("a child was being searched");
+                self.undo_move(m, count);
+                if let Some((child_score, mut moves)) = child
+                    && child_score > node.best_score
+                {
+                    node.best_score = child_score;
+                    moves.push_front(m);
+                    node.best_moves = moves;
+
+                    if let Score::Win = child_score {
+                        node.moves.clear();
+                    }
+                }
+            }
+
+            let Some(m) = node.moves.pop() else {
+                let node = stack.pop().expect("the stack is not empty");
+                let result = (node.best_score, node.best_moves);
+                match stack.last() {
+                    None => return result,
+                    Some(parent) => {
+                        let (_, _, hash) = parent.pending.expect("a child was being searched");
+                        ctx.store(hash, node.depth, result.0);
+                        returned = Some(Some(result));
+                    }
+                }
+                continue;
+            };
+
+            let (best_score, depth) = (node.best_score, node.depth - 1);
+            let parent = ctx.trace_parent(self);
+            let count = self.pour(m);
+            ctx.trace_move(parent, self, m);
+            // The score of a board searched at least as deep is an upper bound of what this
+            // search could find, so the board can be skipped if that doesn't beat the best.
+            let hash = match ctx.table.as_deref() {
+                Some(table) => {
+                    let hash = self.canonical_hash();
+                    if table
+                        .get(&hash)
+                        .is_some_and(|&(d, s)| d >= depth && s <= best_score)
+                    {
+                        ctx.table_hits += 1;
+                        node.pending = Some((m, count, None));
+                        returned = Some(None);
+                        continue;
+                    }
+                    Some(hash)
+                }
+                None => None,
+            };
+            node.pending = Some((m, count, hash));
+            match self.dfs_node(depth, ctx.score(self), ctx) {
+                Ok(child) => stack.push(child),
+                Err(leaf) => {
+                    ctx.store(hash, depth, leaf.0);
+                    returned = Some(Some(leaf));
+                }
+            }
+        }
+    }
+
+    /// Starts searching the current board `depth` moves deep, returning the node to push on the
+    /// stack of `dfs`, or the result right away if there is nothing to search.
+    fn dfs_node(
+        &mut self,
+        depth: u32,
+        score: Score,
+        ctx: &mut SearchContext<C>,
+    ) -> Result<DfsNode, (Score, VecDeque<Move>)> {
+        if ctx.interrupted() || depth == 0 {
+            return Err((score, VecDeque::new()));
+        }
+
+        let forced = ctx
+            .config
+            .apply_forced
+            .then(|| self.forced_moves().first().copied());
+        let mut moves: Vec<Move> = match forced.flatten() {
+            Some(m) => vec![m],
+            None => self.moves().collect(),
+        };
+        if ctx.config.order_moves {
+            // The sort is stable, so equally scored moves keep their order.
+            moves.sort_by_cached_key(|&m| {
+                let count = self.pour(m);
+                let score = ctx.score(self);
+                self.undo_move(m, count);
+                Reverse(score)
+            });
+        }
+        // Moves are popped from the back.
+        moves.reverse();
+
+        Ok(DfsNode {
+            depth,
+            moves,
+            pending: None,
+            best_score: score,
+            best_moves: VecDeque::new(),
+        })
+    }
+
+    /// Looks for a way off a plateau, where no line within `depth` moves improves on the board.
+    /// Moves to boards that weren't committed to yet are searched one ply deeper than that, and
+    /// the best one is returned with the line following it, as long as it doesn't lose ground.
+    fn lateral(
+        &mut self,
+        depth: u32,
+        committed: &HashMap<u64, usize>,
+        ctx: &mut SearchContext<C>,
+    ) -> Option<VecDeque<Move>> {
+        let score = ctx.score(self);
+        let mut best: Option<(Score, VecDeque<Move>)> = None;
+        for m in self.moves().collect::<Vec<_>>() {
+            let parent = ctx.trace_parent(self);
+            let count = self.pour(m);
+            ctx.trace_move(parent, self, m);
+            let child = if committed.contains_key(&self.canonical_hash()) {
+                None
+            } else {
+                Some(self.dfs(depth, ctx.score(self), ctx))
+            };
+            self.undo_move(m, count);
+            if let Some((child_score, mut moves)) = child
+                && child_score >= score
+                && best.as_ref().is_none_or(|(s, _)| child_score > *s)
+            {
+                moves.push_front(m);
+                best = Some((child_score, moves));
+            }
+        }
+        best.map(|(_, moves)| moves)
+    }
+
+    /// Solves the puzzle with a greedy iterative deepening search: up to `iterations` times, the
+    /// best line found within `max_depth` moves is committed. When no line improves on the board,
+    /// a neutral move is played instead so that positions needing some setup can still be
+    /// solved. The returned moves may not win if the budget runs out.
+    pub fn solve(&self, max_depth: u32, iterations: u32) -> VecDeque<Move> {
+        self.iddfs(max_depth, iterations, &mut SearchContext::default())
+    }
+
+    /// Same as [`Puzzle::solve`], but remembers the boards already searched so that positions
+    /// reached through different move orders are not explored again. This returns the same
+    /// moves while visiting far fewer nodes on deep searches.
+    pub fn solve_with_table(&self, max_depth: u32, iterations: u32) -> VecDeque<Move> {
+        let mut table = TranspositionTable::new();
+        self.iddfs(
+            max_depth,
+            iterations,
+            &mut SearchContext {
+                table: Some(&mut table),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Suggests a next move without solving the whole board: the first move of the best line
+    /// found within a few moves, the shortest win if there is one. When no line improves on the
+    /// board, a move that doesn't lose ground is suggested instead. Returns `None` if there is no
+    /// such move.
+    pub fn hint(&self) -> Option<Move> {
+        const DEPTH: u32 = 4;
+
+        let mut game = self.without_history();
+        let mut table = TranspositionTable::new();
+        let mut ctx = SearchContext {
+            table: Some(&mut table),
+            ..Default::default()
+        };
+        let mut best = VecDeque::new();
+        for d in 1..=DEPTH {
+            let (score, moves) = game.dfs(d, game.rank(), &mut ctx);
+            if let Score::Win = score {
+                return moves.front().copied();
+            }
+            best = moves;
+        }
+        if best.is_empty() {
+            let committed = HashMap::from([(game.canonical_hash(), 0)]);
+            best = game.lateral(DEPTH - 1, &committed, &mut ctx)?;
+        }
+        best.front().copied()
+    }
+
+    /// Same as [`Puzzle::solve`], with the boards evaluated by `heuristic` instead of
+    /// [`Puzzle::rank`].
+    pub fn solve_with<H: Heuristic<C>>(
+        &self,
+        max_depth: u32,
+        iterations: u32,
+        heuristic: &H,
+    ) -> VecDeque<Move> {
+        self.iddfs(
+            max_depth,
+            iterations,
+            &mut SearchContext {
+                heuristic: Some(heuristic),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`Puzzle::solve`] searching 5 moves deep, but gives up once `budget` has elapsed
+    /// and returns the moves committed until then, which are always legal but may not win.
+    pub fn solve_timeout(&self, budget: Duration) -> VecDeque<Move> {
+        self.iddfs(
+            5,
+            u32::MAX,
+            &mut SearchContext {
+                deadline: Some(Instant::now() + budget),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`Puzzle::solve_timeout`], but gives up once `cancel` is set, e.g. from another
+    /// thread, instead of after some time.
+    pub fn solve_cancellable(&self, cancel: &AtomicBool) -> VecDeque<Move> {
+        self.iddfs(
+            5,
+            u32::MAX,
+            &mut SearchContext {
+                cancel: Some(cancel),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`Puzzle::solve`], calling `progress` every few thousand nodes with the state of
+    /// the search, e.g. to drive a progress bar.
+    pub fn solve_with_progress(
+        &self,
+        max_depth: u32,
+        iterations: u32,
+        progress: &mut dyn FnMut(SolveProgress),
+    ) -> VecDeque<Move> {
+        self.iddfs(
+            max_depth,
+            iterations,
+            &mut SearchContext {
+                progress: Some(Progress {
+                    callback: progress,
+                    report: SolveProgress {
+                        nodes_visited: 0,
+                        best_score: self.rank(),
+                        depth: 0,
+                        best_len: 0,
+                    },
+                }),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`Puzzle::solve`], but sends each move to `tx` as soon as it is committed instead
+    /// of returning them all at the end, e.g. to play them while the search goes on. When the
+    /// search wins, the moves sent are exactly the ones [`Puzzle::solve`] returns. If it ends up
+    /// going back to a board it already committed to, the detour has been sent already, while
+    /// [`Puzzle::solve`] would drop it.
+    pub fn solve_streaming(&self, max_depth: u32, iterations: u32, tx: Sender<Move>) {
+        self.iddfs(
+            max_depth,
+            iterations,
+            &mut SearchContext {
+                stream: Some(&tx),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Same as [`Puzzle::solve_with_table`], but gives up once the search exceeds one of the
+    /// bounds of `config`, returning the moves committed until then, which are always legal but
+    /// may not win.
+    pub fn solve_with_config(
+        &self,
+        max_depth: u32,
+        iterations: u32,
+        config: &SolveConfig,
+    ) -> VecDeque<Move> {
+        let mut table = TranspositionTable::new();
+        self.iddfs(
+            max_depth,
+            iterations,
+            &mut SearchContext {
+                table: Some(&mut table),
+                config: *config,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`Puzzle::solve_with_table`], also returning counters describing the search, to
+    /// tune its parameters or compare it with other algorithms.
+    pub fn solve_with_stats(
+        &self,
+        max_depth: u32,
+        iterations: u32,
+    ) -> (VecDeque<Move>, SolveStats) {
+        let start = Instant::now();
+        let mut table = TranspositionTable::new();
+        let mut ctx = SearchContext {
+            table: Some(&mut table),
+            ..Default::default()
+        };
+        let moves = self.iddfs(max_depth, iterations, &mut ctx);
+        let stats = SolveStats {
+            nodes_visited: ctx.nodes,
+            max_depth_reached: ctx.max_depth_reached,
+            iterations_used: ctx.iterations,
+            elapsed: start.elapsed(),
+            transpositions_hit: ctx.table_hits,
+        };
+        (moves, stats)
+    }
+
+    // IDDFS
+    fn iddfs(&self, max_depth: u32, iterations: u32, ctx: &mut SearchContext<C>) -> VecDeque<Move> {
+        let mut all_moves = VecDeque::new();
+        let mut count = 0;
+        let mut game = self.without_history();
+        // Boards committed to so far, with the number of moves it took to reach them.
+        let mut committed = HashMap::from([(game.canonical_hash(), 0)]);
+        while count < iterations {
+            let mut best_moves = VecDeque::new();
+            for d in 0..max_depth {
+                ctx.set_progress(&game, d, all_moves.len());
+                ctx.max_depth_reached = ctx.max_depth_reached.max(d);
+                let (score, moves) = match ctx.split {
+                    Split::Sequential => game.dfs(d, ctx.score(&game), ctx),
+                    Split::Threads => game.dfs_parallel(d, game.rank()),
+                    #[cfg(feature = "rayon")]
+                    Split::Rayon => game.dfs_rayon(d, game.rank()),
+                };
+                if ctx.halted {
+                    // The interrupted search may have missed better lines, so drop it.
+                    info!(
+                        "Interrupted after committing {} unique states.",
+                        committed.len()
+                    );
+                    return all_moves;
+                }
+                if let Score::Win = score {
+                    ctx.iterations = count + 1;
+                    ctx.send_moves(&moves);
+                    all_moves.extend(moves);
+                    info!("Found a winner in {} moves.", all_moves.len());
+                    return all_moves;
+                }
+                best_moves = moves;
+            }
+            if best_moves.is_empty() {
+                ctx.max_depth_reached = ctx.max_depth_reached.max(max_depth);
+                match game.lateral(max_depth.saturating_sub(1), &committed, ctx) {
+                    _ if ctx.halted => {
+                        info!(
+                            "Interrupted after committing {} unique states.",
+                            committed.len()
+                        );
+                        return all_moves;
+                    }
+                    Some(moves) => best_moves = moves,
+                    None => {
+                        // Every move loses ground or goes back to a board already seen.
+                        info!(
+                            "No improving move found after committing {} unique states.",
+                            committed.len()
+                        );
+                        return all_moves;
+                    }
+                }
+            }
+            for m in &best_moves {
+                game.pour(*m);
+            }
+            ctx.send_moves(&best_moves);
+            all_moves.extend(best_moves);
+            count += 1;
+            ctx.iterations = count;
+
+            // Going back to a board seen before means the search would keep cycling, so drop the
+            // detour and stop there.
+            match committed.entry(game.canonical_hash()) {
+                Entry::Occupied(entry) => {
+                    all_moves.truncate(*entry.get());
+                    info!(
+                        "Stuck in a cycle after committing {} unique states.",
+                        committed.len()
+                    );
+                    return all_moves;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(all_moves.len());
+                }
+            }
+        }
+        info!(
+            "No winner found after committing {} unique states.",
+            committed.len()
+        );
+        all_moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::BuildHasher;
+
+    use super::*;
+
+    /// Board the solver was first written against.
+    pub(crate) fn main_board() -> Puzzle {
+        Puzzle::new(
+            4,
+            &[
+                vec![1, 2, 3, 4],
+                vec![3, 5, 3, 1],
+                vec![6, 1, 2, 5],
+                vec![6, 3, 2, 5],
+                vec![6, 5, 4, 6],
+                vec![2, 1, 4, 4],
+                vec![],
+                vec![],
+            ],
+        )
+    }
+
+    /// Small generated boards, with their seed and the length of their shortest solution as found
+    /// by BFS, to check the other searches against.
+    pub(crate) fn small_boards() -> impl Iterator<Item = (u64, Puzzle, usize)> {
+        (0..10).map(|seed| {
+            let p = Puzzle::generate_seeded(3, 4, 2, seed);
+            let shortest = p.solve_bfs().unwrap().len();
+            (seed, p, shortest)
+        })
+    }
+
+    /// Move from `from` to `to`, its count left to the board.
+    pub(crate) fn mv(from: usize, to: usize) -> Move {
+        Move { from, to, count: 0 }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let p = main_board();
+        let json = serde_json::to_string(&p).unwrap();
+        let q: Puzzle = serde_json::from_str(&json).unwrap();
+        assert_eq!(q.capacity, p.capacity);
+        assert_eq!(q.state, p.state);
+        assert_eq!(q.colors_count, p.colors_count);
+
+        let wrong = json.replace(r#""1":4"#, r#""1":3"#);
+        assert_ne!(wrong, json);
+        let error = serde_json::from_str::<Puzzle>(&wrong).unwrap_err();
+        assert!(error.to_string().contains("color 1"), "{error}");
+    }
+
+    #[test]
+    fn complete_columns_of_mixed_sizes_are_not_deadlocked() {
+        let p = Puzzle::new(
+            vec![4, 2, 2, 2],
+            &[vec![1, 1], vec![], vec![2, 2], vec![2, 2]],
+        );
+        assert!(!p.is_deadlocked());
+        assert!(p.legal_moves().contains(&Move {
+            from: 0,
+            to: 1,
+            count: 2
+        }));
+    }
+
+    #[test]
+    fn colors_above_nine_print_in_aligned_cells() {
+        let p = Puzzle::new(2, &[vec![10, 45], vec![3], vec![35, 36]]);
+        assert_eq!(p.to_string(), "[2d] [  ] [24]\n[ A] [ 3] [ Z]");
+        assert_eq!(p.to_string().parse::<Puzzle>(), Ok(p));
+
+        let far = Puzzle::new(1, &[vec![40], vec![255], vec![4096]]);
+        assert_eq!(far.to_string(), "[  28] [  ff] [1000]");
+        assert_eq!(far.to_string().parse::<Puzzle>(), Ok(far));
+    }
+
+    #[test]
+    fn do_move_rejects_illegal_pours() {
+        let mut p = Puzzle::new(3, &[vec![1, 2], vec![1], vec![]]);
+        let before = p.state.clone();
+        assert_eq!(p.do_move(mv(2, 0)), Err(PuzzleError::EmptyColumn(2)));
+        assert_eq!(p.do_move(mv(1, 1)), Err(PuzzleError::SameColumn(1)));
+        assert_eq!(
+            p.do_move(mv(0, 1)),
+            Err(PuzzleError::ColorMismatch { from: 0, to: 1 })
+        );
+        assert_eq!(p.state, before);
+        assert!(p.history().is_empty());
+    }
+
+    #[test]
+    fn table_visits_fewer_nodes() {
+        let p = main_board();
+        let mut ctx = SearchContext::default();
+        let moves = p.iddfs(5, 100, &mut ctx);
+        let (with_table, stats) = p.solve_with_stats(5, 100);
+        assert_eq!(with_table, moves);
+        assert!(stats.nodes_visited < ctx.nodes);
+    }
+
+    #[test]
+    fn stuck_boards_stop_right_away() {
+        let p = Puzzle::new(2, &[vec![1, 2], vec![2, 1]]);
+        let (moves, stats) = p.solve_with_stats(5, u32::MAX);
+        assert!(moves.is_empty());
+        assert_eq!(stats.iterations_used, 0);
+    }
+
+    #[test]
+    fn plateaus_are_left_with_neutral_moves() {
+        let p = Puzzle::new(3, &[vec![2, 2], vec![0, 1, 0], vec![1, 2, 1], vec![0]]);
+        let mut game = p.without_history();
+        let (_, moves) = game.dfs(1, game.rank(), &mut SearchContext::default());
+        assert!(
+            moves.is_empty(),
+            "no single move should improve on the board"
+        );
+        let moves = p.solve(2, 100);
+        assert_eq!(p.verify_solution(&Vec::from(moves)), Ok(()));
+    }
+
+    #[test]
+    fn undo_move_restores_the_board() {
+        let p = main_board();
+        for m in p.legal_moves() {
+            let mut game = p.clone();
+            let count = game.do_move(m).unwrap();
+            game.undo_move(m, count);
+            assert_eq!(game.state, p.state);
+            assert_eq!(game.colors_count, p.colors_count);
+        }
+    }
+
+    #[test]
+    fn permuted_columns_share_the_canonical_form() {
+        let p = main_board();
+        let mut columns: Vec<Vec<Color>> = p.state.iter().map(|c| c.to_vec()).collect();
+        columns.reverse();
+        columns.swap(0, 3);
+        let q = Puzzle::new(4, &columns);
+        assert_ne!(q.state, p.state);
+        assert_eq!(q.canonical(), p.canonical());
+    }
+
+    #[test]
+    fn undoing_a_solution_goes_back_to_the_start() {
+        let p = Puzzle::new(3, &[vec![2, 2], vec![0, 1, 0], vec![1, 2, 1], vec![0]]);
+        let moves = p.solve_bfs().unwrap();
+        let mut game = p.clone();
+        for &m in &moves {
+            game.do_move(m).unwrap();
+        }
+        assert!(game.is_solved());
+        assert_eq!(game.history().len(), moves.len());
+        while game.undo().is_some() {}
+        assert_eq!(game.state, p.state);
+        assert_eq!(game.colors_count, p.colors_count);
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn verify_solution_finds_the_first_bad_step() {
+        let p = Puzzle::new(3, &[vec![2, 2], vec![0, 1, 0], vec![1, 2, 1], vec![0]]);
+        let moves = Vec::from(p.solve_bfs().unwrap());
+        assert_eq!(p.verify_solution(&moves), Ok(()));
+
+        let mut corrupted = moves.clone();
+        corrupted[1] = mv(corrupted[1].from, corrupted[1].from);
+        assert_eq!(
+            p.verify_solution(&corrupted),
+            Err(PuzzleError::IllegalStep {
+                index: 1,
+                error: Box::new(PuzzleError::SameColumn(corrupted[1].from)),
+            })
+        );
+        assert_eq!(
+            p.verify_solution(&moves[..moves.len() - 1]),
+            Err(PuzzleError::NotSolved {
+                moves: moves.len() - 1
+            })
+        );
+    }
+
+    #[test]
+    fn can_move_follows_the_rules() {
+        let p = Puzzle::new(3, &[vec![1, 2], vec![2], vec![1, 1, 1], vec![]]);
+        assert!(p.can_move(mv(0, 3)), "empty destination");
+        assert!(p.can_move(mv(0, 1)), "same color");
+        assert!(!p.can_move(mv(2, 1)), "other color");
+        assert!(!p.can_move(mv(0, 2)), "full destination");
+        assert!(!p.can_move(mv(0, 0)), "same column");
+        assert!(!p.can_move(mv(3, 0)), "empty source");
+        for from in 0..4 {
+            for to in 0..4 {
+                let m = mv(from, to);
+                assert_eq!(p.can_move(m), p.with_move(m).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn pours_are_capped_by_the_free_slots() {
+        let p = Puzzle::new(4, &[vec![1, 2, 2, 2], vec![3, 2], vec![3, 3, 2], vec![]]);
+        let runs: Vec<usize> = (0..4).map(|i| p.top_run_length(i)).collect();
+        assert_eq!(runs, [3, 1, 1, 0]);
+        assert_eq!(p.pour_count(mv(0, 1)), 2);
+        assert_eq!(p.pour_count(mv(0, 2)), 1);
+        assert_eq!(p.pour_count(mv(0, 3)), 3);
+        assert_eq!(p.pour_count(mv(2, 1)), 1);
+        assert_eq!(p.pour_count(mv(1, 0)), 0);
+    }
+
+    #[test]
+    fn free_space_counts_the_empty_slots() {
+        let p = Puzzle::new(3, &[vec![1, 1, 1], vec![2], vec![]]);
+        let free: Vec<usize> = (0..3).map(|i| p.free_space(i)).collect();
+        assert_eq!(free, [0, 2, 3]);
+        let full: Vec<bool> = (0..3).map(|i| p.is_full(i)).collect();
+        assert_eq!(full, [true, false, false]);
+    }
+
+    #[test]
+    fn solve_timeout_stays_within_its_budget() {
+        let p = Puzzle::generate_seeded(4, 8, 2, 31);
+        let start = Instant::now();
+        let moves = p.solve_timeout(Duration::from_millis(20));
+        assert!(start.elapsed() < Duration::from_millis(500));
+        p.without_history().apply_moves(&Vec::from(moves)).unwrap();
+    }
+
+    #[test]
+    fn solve_cancellable_stops_once_cancelled() {
+        let p = Puzzle::generate_seeded(4, 10, 2, 32);
+        let cancel = AtomicBool::new(false);
+        let start = Instant::now();
+        let moves = std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                cancel.store(true, Ordering::Relaxed);
+            });
+            p.solve_cancellable(&cancel)
+        });
+        assert!(start.elapsed() < Duration::from_millis(500));
+        p.without_history().apply_moves(&Vec::from(moves)).unwrap();
+    }
+
+    #[test]
+    fn solve_with_stats_counts_the_search() {
+        let (moves, stats) = main_board().solve_with_stats(5, 100);
+        assert!(!moves.is_empty());
+        assert!(stats.nodes_visited > 0);
+        assert!(stats.iterations_used > 0);
+        assert!(stats.elapsed > Duration::ZERO);
+    }
+
+    #[test]
+    fn hint_plays_the_winning_move() {
+        let p = Puzzle::new(3, &[vec![1, 1, 1], vec![2, 2, 3], vec![3, 3], vec![]]);
+        let m = p.hint().unwrap();
+        assert_eq!((m.from, m.to), (1, 2));
+        assert!(p.with_move(m).unwrap().is_solved());
+    }
+
+    #[test]
+    fn complete_columns_are_locked() {
+        let mut p = Puzzle::new(3, &[vec![1, 1, 1], vec![2, 2], vec![2], vec![]]);
+        assert_eq!(p.column_moves(0).count(), 0);
+        assert!(p.moves().all(|m| m.from != 0));
+        p.set_lock_complete(false);
+        assert_eq!(p.column_moves(0).count(), 1);
+    }
+
+    #[test]
+    fn only_one_empty_column_is_poured_into() {
+        let p = Puzzle::new(2, &[vec![1, 2], vec![2, 1], vec![], vec![], vec![]]);
+        let moves: Vec<(usize, usize)> = p.moves().map(|m| (m.from, m.to)).collect();
+        assert_eq!(moves, [(0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn counts_stay_consistent_through_moves() {
+        let mut p = main_board();
+        for i in 0..20 {
+            let moves = p.legal_moves();
+            p.do_move(moves[i % moves.len()]).unwrap();
+            p.debug_check_counts();
+        }
+        while p.undo().is_some() {
+            p.debug_check_counts();
+        }
+        assert_eq!(p.colors_count, recount(&main_board().state, None));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "colors_count no longer matches the board")]
+    fn drifting_counts_are_caught() {
+        let mut p = main_board();
+        p.colors_count.insert(1, 3);
+        p.do_move(mv(0, 6)).unwrap();
+    }
+
+    #[test]
+    fn streamed_moves_are_the_solution() {
+        let p = main_board();
+        let (tx, rx) = std::sync::mpsc::channel();
+        p.solve_streaming(5, 100, tx);
+        let streamed: Vec<Move> = rx.iter().collect();
+        let moves = Vec::from(p.solve(5, 100));
+        assert_eq!(p.verify_solution(&moves), Ok(()));
+        assert_eq!(streamed, moves);
+    }
+
+    #[test]
+    fn shuffled_twins_are_equal_and_hash_the_same() {
+        let p = main_board();
+        let mut columns: Vec<Vec<Color>> = p.state.iter().map(|c| c.to_vec()).collect();
+        columns.rotate_left(3);
+        let twin = Puzzle::new(4, &columns);
+        assert_eq!(twin, p);
+        let hasher = std::hash::RandomState::new();
+        assert_eq!(hasher.hash_one(&twin), hasher.hash_one(&p));
+
+        let other = p.with_move(mv(0, 6)).unwrap();
+        assert_ne!(other, p);
+        let set = std::collections::HashSet::from([p]);
+        assert!(set.contains(&twin));
+        assert!(!set.contains(&other));
+    }
+
+    #[test]
+    fn max_nodes_stops_the_search_early() {
+        let p = main_board();
+        let config = SolveConfig {
+            max_nodes: Some(10),
+            ..Default::default()
+        };
+        let moves = Vec::from(p.solve_with_config(5, 100, &config));
+        let full = p.solve_with_table(5, 100);
+        assert!(moves.len() < full.len());
+        p.without_history().apply_moves(&moves).unwrap();
+        assert_eq!(Vec::from(p.solve_with_config(5, 100, &config)), moves);
+    }
+
+    #[test]
+    fn move_ordering_visits_fewer_nodes() {
+        let p = main_board();
+        let count = |order_moves| {
+            let mut table = TranspositionTable::new();
+            let mut ctx = SearchContext {
+                table: Some(&mut table),
+                config: SolveConfig {
+                    order_moves,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let moves = p.iddfs(5, 100, &mut ctx);
+            assert_eq!(p.verify_solution(&Vec::from(moves)), Ok(()));
+            ctx.nodes
+        };
+        assert!(count(true) < count(false));
+    }
+
+    #[test]
+    fn forced_moves_complete_a_color() {
+        let p = Puzzle::new(4, &[vec![1, 1, 1], vec![2, 2, 1], vec![2, 2], vec![]]);
+        assert_eq!(
+            p.forced_moves(),
+            [Move {
+                from: 1,
+                to: 0,
+                count: 1
+            }]
+        );
+
+        // Pouring the 1 onto the others leaves one of them buried under a 2.
+        let p = Puzzle::new(4, &[vec![1, 1], vec![2, 2, 1], vec![1, 2], vec![2]]);
+        assert!(p.forced_moves().is_empty());
+    }
+
+    #[test]
+    fn deep_searches_dont_overflow() {
+        // Unsolvable, but some move can always be played, so the search goes all the way down.
+        let p = Puzzle::new(3, &[vec![1, 2, 1], vec![2, 1, 2], vec![1, 2]]);
+        let mut game = p.without_history();
+        let mut table = TranspositionTable::new();
+        let mut ctx = SearchContext {
+            table: Some(&mut table),
+            ..Default::default()
+        };
+        let (score, moves) = game.dfs(20_000, game.rank(), &mut ctx);
+        assert_ne!(score, Score::Win);
+        assert!(moves.is_empty());
+        assert_eq!(ctx.nodes, 20_001);
+        assert_eq!(game.state, p.state);
+
+        // The solutions found by the recursive search this replaced.
+        let pinned = |moves: &[(usize, usize, usize)]| -> VecDeque<Move> {
+            moves
+                .iter()
+                .map(|&(from, to, count)| Move { from, to, count })
+                .collect()
+        };
+        let p = Puzzle::new(3, &[vec![2, 2], vec![0, 1, 0], vec![1, 2, 1], vec![0]]);
+        let expected = pinned(&[(1, 3, 1), (2, 1, 1), (2, 0, 1), (1, 2, 2), (1, 3, 1)]);
+        assert_eq!(p.solve(1_000, 100), expected);
+        assert_eq!(p.solve(5, 100), expected);
+        let expected = pinned(&[
+            (0, 6, 1),
+            (4, 7, 1),
+            (4, 6, 1),
+            (5, 6, 2),
+            (1, 5, 1),
+            (1, 0, 1),
+            (4, 1, 1),
+            (2, 1, 1),
+            (4, 7, 1),
+            (1, 4, 3),
+            (3, 4, 1),
+            (0, 1, 2),
+            (2, 0, 1),
+            (3, 0, 1),
+            (3, 1, 1),
+            (5, 2, 2),
+            (0, 5, 3),
+            (2, 0, 3),
+            (2, 3, 1),
+            (3, 7, 2),
+        ]);
+        assert_eq!(main_board().solve(5, 100), expected);
+    }
+
+    #[test]
+    fn moves_display_their_count() {
+        let m = Move {
+            from: 4,
+            to: 6,
+            count: 2,
+        };
+        assert_eq!(m.to_string(), "4 -> 6 (×2)");
+        assert_eq!(mv(4, 6).to_string(), "4 -> 6");
+    }
+
+    #[test]
+    fn apply_moves_stops_at_the_illegal_move() {
+        let start = Puzzle::new(2, &[vec![1, 2], vec![1, 2], vec![]]);
+        let mut p = start.clone();
+        p.apply_moves(&[mv(0, 2), mv(1, 2), mv(0, 1)]).unwrap();
+        assert!(p.is_solved());
+
+        let mut p = start.clone();
+        assert_eq!(
+            p.apply_moves(&[mv(0, 2), mv(0, 1), mv(1, 2)]),
+            Err(PuzzleError::IllegalStep {
+                index: 1,
+                error: Box::new(PuzzleError::ColumnFull(1)),
+            })
+        );
+        assert_eq!(p.history().len(), 1);
+    }
+
+    #[test]
+    fn with_move_leaves_the_board_alone() {
+        let p = main_board();
+        let next = p.with_move(mv(0, 6)).unwrap();
+        assert_eq!(p.state, main_board().state);
+        assert!(p.history().is_empty());
+        assert_eq!(next.column(6), [4]);
+        assert_eq!(next.history().len(), 1);
+        assert_eq!(p.with_move(mv(6, 0)), Err(PuzzleError::EmptyColumn(6)));
+    }
+
+    #[test]
+    fn states_along_ends_on_the_solved_board() {
+        let p = Puzzle::new(2, &[vec![1, 2], vec![1, 2], vec![]]);
+        let moves = [mv(0, 2), mv(1, 2), mv(0, 1)];
+        let states: Vec<Puzzle> = p.states_along(&moves).collect();
+        assert_eq!(states.len(), moves.len() + 1);
+        assert_eq!(states[0].state, p.state);
+        assert!(states.last().unwrap().is_solved());
+        assert!(states[..moves.len()].iter().all(|s| !s.is_solved()));
+    }
+
+    #[test]
+    fn counts_buried_balls() {
+        // Every ball but the top one is buried, except for the 4s at the top of the last column.
+        assert_eq!(main_board().buried_count(), 17);
+        let p = Puzzle::new(3, &[vec![1, 1, 1], vec![2, 2], vec![1, 2, 2]]);
+        assert_eq!(p.buried_count(), 1);
+    }
+
+    #[test]
+    fn state_hash_ignores_the_order_of_the_columns() {
+        let p = main_board();
+        let mut columns: Vec<Vec<Color>> = p.state.iter().map(|c| c.to_vec()).collect();
+        columns.swap(0, 5);
+        columns.swap(6, 1);
+        let twin = Puzzle::new(4, &columns);
+        assert_eq!(twin.state_hash(), p.state_hash());
+        assert_ne!(p.with_move(mv(0, 6)).unwrap().state_hash(), p.state_hash());
+    }
+
+    #[test]
+    fn neighbors_are_one_move_away() {
+        let p = main_board();
+        let neighbors: Vec<(Move, Puzzle)> = p.neighbors().collect();
+        assert!(!neighbors.is_empty());
+        for (m, next) in neighbors {
+            assert_ne!(next, p);
+            let mut game = p.clone();
+            assert_eq!(game.do_move(m), Ok(m.count));
+            assert_eq!(game.state, next.state);
+        }
+        // Pouring the only ball of a column into an empty one gives back the same board.
+        let p = Puzzle::new(2, &[vec![1, 1], vec![2], vec![2], vec![]]);
+        assert!(p.neighbors().all(|(m, _)| m.to != 3));
+    }
+
+    #[test]
+    fn jokers_match_any_color() {
+        let mut p = Puzzle::new(3, &[vec![1], vec![2, 9], vec![9], vec![2, 1], vec![1, 2]]);
+        p.set_joker(Some(9));
+        assert_eq!(p.num_colors(), 2);
+        // Onto a joker, and a joker onto a ball.
+        assert_eq!(p.clone().do_move(mv(0, 1)), Ok(1));
+        assert_eq!(p.clone().do_move(mv(2, 3)), Ok(1));
+        // Other balls still have to match.
+        assert_eq!(
+            p.clone().do_move(mv(0, 4)),
+            Err(PuzzleError::ColorMismatch { from: 0, to: 4 })
+        );
+
+        // A run keeps the jokers mixed in, and moves with them.
+        let mut p = Puzzle::new(4, &[vec![2, 1, 9, 1], vec![1], vec![]]);
+        p.set_joker(Some(9));
+        assert_eq!(p.top_run_length(0), 3);
+        assert_eq!(p.clone().do_move(mv(0, 1)), Ok(3));
+        assert_eq!(p.clone().do_move(mv(0, 2)), Ok(3));
+
+        let mut p = Puzzle::new(3, &[vec![1, 9, 1], vec![2, 2], vec![9]]);
+        p.set_joker(Some(9));
+        assert!(p.is_solved());
+    }
+
+    #[test]
+    fn counts_the_completed_colors() {
+        let p = Puzzle::new(
+            3,
+            &[vec![1, 1, 1], vec![2, 2], vec![2, 3, 3], vec![3], vec![]],
+        );
+        assert_eq!(p.completed_columns(), 1);
+        assert_eq!(p.remaining_colors(), 2);
+        let p = p.with_move(mv(2, 3)).unwrap();
+        assert_eq!(p.completed_columns(), 2);
+        assert_eq!(p.remaining_colors(), 1);
+    }
+
+    #[test]
+    fn inverse_moves_pour_the_balls_back() {
+        // Emptying the source makes room for the balls to come back on their own.
+        let p = Puzzle::new(3, &[vec![2, 2], vec![1, 2], vec![1, 1], vec![]]);
+        let mut game = p.clone();
+        let m = mv(0, 3);
+        let count = game.do_move(m).unwrap();
+        assert_eq!(game.do_move(m.inverse(count)), Ok(count));
+        assert_eq!(game.state, p.state);
+
+        // Here the ball left at the source has another color, so only undo_move brings the 2
+        // back.
+        let mut game = p.clone();
+        let m = mv(1, 0);
+        let count = game.do_move(m).unwrap();
+        assert!(!game.can_move(m.inverse(count)));
+        game.undo_move(m, count);
+        assert_eq!(game.state, p.state);
+    }
+
+    #[test]
+    fn single_ball_moves_take_longer() {
+        let run = Puzzle::new(4, &[vec![0, 1, 1, 0], vec![1, 0, 0, 1], vec![]]);
+        let mut single = run.clone();
+        single.set_move_mode(MoveMode::Single);
+        let mut pair = Puzzle::new(3, &[vec![2, 1, 1], vec![]]);
+        assert_eq!(pair.pour_count(mv(0, 1)), 2);
+        pair.set_move_mode(MoveMode::Single);
+        assert_eq!(pair.pour_count(mv(0, 1)), 1);
+
+        let run_moves = Vec::from(run.solve_bfs().unwrap());
+        let single_moves = Vec::from(single.solve_bfs().unwrap());
+        assert_eq!(run_moves.len(), 5);
+        assert_eq!(single_moves.len(), 8);
+        assert!(single.verify_solution(&run_moves).is_err());
+        assert!(single_moves.iter().all(|m| m.count == 1));
+        assert_eq!(single.verify_solution(&single_moves), Ok(()));
+    }
+}
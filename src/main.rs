@@ -1,221 +1,403 @@
-use std::cmp::min;
-use std::collections::HashMap;
-use std::collections::VecDeque;
-use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-type Color = u32;
-type Column = Vec<Color>;
+use clap::{Parser, Subcommand, ValueEnum};
+use sorting::{Move, Puzzle};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-enum Score {
-    Score(usize),
-    Win,
-}
+const AFTER_HELP: &str = "\
+Boards are read either as a grid of [c] cells like the ones printed by this
+tool, top row first, or as a JSON object like
+{\"column_size\": 4, \"columns\": [[1, 2], []]} for files ending in .json. A
+\"joker\" entry can be added to the JSON object to make a color match any other.
 
-#[derive(Debug, Clone)]
-struct Puzzle {
-    column_size: usize,
-    colors_count: HashMap<Color, usize>,
-    state: Vec<Column>,
-}
+Exit status: 0 on success, 1 if no solution was found within the budget and 2
+if the board cannot be read or is invalid, as well as on usage errors.";
 
-impl fmt::Display for Puzzle {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for i in 0..self.column_size {
-            if i > 0 {
-                writeln!(f)?;
-            }
-            for j in 0..self.state.len() {
-                let col = &self.state[j];
-                if j > 0 {
-                    write!(f, " ")?;
-                }
-                let idx = self.column_size - i - 1;
-                let c = col
-                    .get(idx)
-                    // This is pretty bad since it will only print something meaningful if callers
-                    // passed values from 0 to 9 in the columns, but this is just toy code anyways.
-                    .map(|&x| char::from_digit(x, 10).unwrap_or('?'))
-                    .unwrap_or(' ');
-                write!(f, "[{c}]")?;
-            }
+/// Exit status when the solver gives up without winning.
+const NOT_SOLVED: u8 = 1;
+/// Exit status when the board cannot be read, the same as clap's usage errors.
+const BAD_INPUT: u8 = 2;
+
+/// Prints the messages logged by the library on the standard error.
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", record.args());
         }
-        Ok(())
     }
+
+    fn flush(&self) {}
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct Move(usize, usize);
+/// Solves ball sort puzzles.
+#[derive(Parser)]
+#[command(after_help = AFTER_HELP)]
+struct Cli {
+    /// Number of moves looked ahead by each search of the solver.
+    #[arg(long, global = true, default_value_t = 5)]
+    depth: u32,
+    /// Maximum number of lines committed by the solver.
+    #[arg(long, global = true, default_value_t = 100)]
+    iterations: u32,
+    /// Format of the boards and solutions printed.
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Prints only the moves of solutions, and no diagnostics.
+    #[arg(long, short, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Prints the board and its score after each move of solutions, and more diagnostics.
+    #[arg(long, short, global = true)]
+    verbose: bool,
+    #[command(subcommand)]
+    command: Command,
+}
 
-impl Puzzle {
-    fn new(column_size: usize, init: &[Vec<u32>]) -> Self {
-        let mut colors_count = HashMap::new();
-        let mut state = Vec::new();
+#[derive(Subcommand)]
+enum Command {
+    /// Solves a board and prints the moves.
+    Solve {
+        /// File holding the board, read from the standard input if missing.
+        file: Option<PathBuf>,
+    },
+    /// Solves every board of a file holding several grids separated by blank lines.
+    Batch {
+        /// File holding the boards, read from the standard input if missing.
+        file: Option<PathBuf>,
+    },
+    /// Plays a board in the terminal, reading moves from the standard input.
+    Play {
+        /// File holding the board, a random one being played if missing.
+        file: Option<PathBuf>,
+    },
+    /// Checks that a board is well formed.
+    Validate {
+        /// File holding the board, read from the standard input if missing.
+        file: Option<PathBuf>,
+    },
+    /// Prints a random solvable board.
+    Generate {
+        /// Number of balls of each color, and of slots of each column.
+        #[arg(long, default_value_t = 4)]
+        column_size: usize,
+        /// Number of colors, each starting in its own column.
+        #[arg(long, default_value_t = 6)]
+        colors: usize,
+        /// Number of extra empty columns.
+        #[arg(long, default_value_t = 2)]
+        empty: usize,
+        /// Seed always giving the same board, random if not set.
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+}
 
-        for col in init {
-            let mut vec = Vec::with_capacity(column_size);
-            for &c in &col[..min(column_size, col.len())] {
-                let entry = colors_count.entry(c).or_insert(0);
-                *entry += 1;
-                vec.push(c);
-            }
-            state.push(vec);
-        }
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Grid of [c] cells, with the moves of solutions listed one by one.
+    Text,
+    /// Structured JSON objects.
+    Json,
+    /// Like text, with the balls drawn as emojis.
+    Emoji,
+    /// Like text, with the balls drawn in 24-bit terminal colors.
+    Ansi,
+}
 
-        Puzzle {
-            column_size,
-            colors_count,
-            state,
+impl Format {
+    /// Renders a board, which is never asked of the JSON format.
+    fn board(self, p: &Puzzle) -> String {
+        match self {
+            Format::Text | Format::Json => p.to_string(),
+            Format::Emoji => p.render_emoji(),
+            Format::Ansi => p.render_ansi(),
         }
     }
+}
 
-    fn rank(&self) -> Score {
-        let mut score: usize = 0;
-        let mut done = true;
-
-        for (i, col) in self.state.iter().enumerate() {
-            // Adding the number of moves to the score to promote states that are not stuck.
-            score += self.column_moves(i).count();
-            // We use self.state.len() as a multiplier to ensure the various conditions below
-            // (empty columns, columns with just one color, columns fully sorted with all the
-            // entries of that color) dominate over just being able to move items.
-            if let Some(&c) = col.last() {
-                if col.iter().all(|&c2| c2 == c) {
-                    // Column containing just a single color
-                    if col.len() == self.colors_count[&c] {
-                        // Column with all the entries of a single color
-                        score += 1000 * self.state.len();
-                    } else {
-                        score += 100 * self.state.len();
-                        done = false;
-                    }
-                } else {
-                    done = false;
-                }
-            } else {
-                // Empty column
-                score += 10 * self.state.len();
-            }
-        }
+/// Parses a board in the format given by `json`.
+fn parse(input: &str, json: bool) -> Result<Puzzle, String> {
+    if json {
+        Puzzle::from_json(input).map_err(|e| e.to_string())
+    } else {
+        input
+            .parse()
+            .map_err(|e: sorting::ParsePuzzleError| e.to_string())
+    }
+}
 
-        if done {
-            return Score::Win;
+/// Reads the whole of `path`, or of the standard input.
+fn read_input(path: Option<&Path>) -> Result<String, String> {
+    match path {
+        Some(path) => fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display())),
+        None => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| format!("<stdin>: {e}"))?;
+            Ok(input)
         }
-        Score::Score(score)
     }
+}
 
-    fn column_moves(&self, col: usize) -> impl Iterator<Item = Move> {
-        let src = &self.state[col];
-        let iter;
-
-        if let Some(&c) = src.last() {
-            iter = Some(
-                self.state
-                    .iter()
-                    .enumerate()
-                    .filter(move |(i, _)| *i != col)
-                    .filter(move |(_, dst)| dst.last().is_none_or(|&c2| c2 == c))
-                    .filter(|(_, dst)| dst.len() < self.column_size)
-                    .map(move |(i, _)| Move(col, i)),
-            );
+/// Name of `path` in error messages.
+fn input_name(path: Option<&Path>) -> String {
+    path.map_or("<stdin>".to_string(), |path| path.display().to_string())
+}
+
+/// Reads the board from `path`, or from the standard input.
+fn read_puzzle(path: Option<&Path>) -> Result<Puzzle, String> {
+    let input = read_input(path)?;
+    let json = match path {
+        Some(path) => path.extension().is_some_and(|ext| ext == "json"),
+        None => input.trim_start().starts_with('{'),
+    };
+    parse(&input, json).map_err(|e| format!("{}: {e}", input_name(path)))
+}
+
+/// Reads grids of boards separated by blank lines from `path`, or from the standard input.
+fn read_boards(path: Option<&Path>) -> Result<Vec<Puzzle>, String> {
+    let input = read_input(path)?;
+    let mut grids = vec![String::new()];
+    for line in input.lines() {
+        if line.trim().is_empty() {
+            if !grids.last().is_some_and(String::is_empty) {
+                grids.push(String::new());
+            }
         } else {
-            iter = None;
+            let grid = grids.last_mut().expect("there is always a grid");
+            grid.push_str(line);
+            grid.push('\n');
         }
-
-        iter.into_iter().flatten()
     }
+    grids.retain(|grid| !grid.is_empty());
+    grids
+        .iter()
+        .enumerate()
+        .map(|(i, grid)| {
+            parse(grid, false).map_err(|e| format!("{}: board {i}: {e}", input_name(path)))
+        })
+        .collect()
+}
 
-    fn moves(&self) -> impl Iterator<Item = Move> {
-        self.state
-            .iter()
-            .enumerate()
-            .flat_map(|(i, _)| self.column_moves(i))
+/// Solves each of `boards`, printing a line with the moves of each one. With the JSON format,
+/// each line holds the solution of a board as JSON, or `null` if none was found.
+fn solve_all(boards: &[Puzzle], depth: u32, iterations: u32, format: Format) -> ExitCode {
+    let solutions = Puzzle::solve_batch_parallel(boards, depth, iterations);
+    for (i, moves) in solutions.iter().enumerate() {
+        match moves {
+            Some(moves) if format == Format::Json => {
+                let moves: Vec<Move> = moves.iter().copied().collect();
+                println!("{}", boards[i].solution_to_json(&moves));
+            }
+            None if format == Format::Json => println!("null"),
+            Some(moves) => {
+                let moves: Vec<String> = moves.iter().map(Move::to_string).collect();
+                println!(
+                    "board {i}: solved in {} moves: {}",
+                    moves.len(),
+                    moves.join(", ")
+                );
+            }
+            None => println!("board {i}: no solution found"),
+        }
+    }
+    if solutions.iter().all(Option::is_some) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(NOT_SOLVED)
     }
+}
 
-    fn do_move(&mut self, Move(from, to): Move) {
-        let &color = self.state[from]
-            .last()
-            .expect("cannot move from an empty column");
+/// How much `solve` prints besides the moves.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Verbosity {
+    /// Only the moves.
+    Quiet,
+    /// The moves between the initial and the final board.
+    Normal,
+    /// The board and its score after each move.
+    Verbose,
+}
 
-        while self.state[to].len() < self.column_size
-            && let Some(c) = self.state[from].pop_if(|c2| *c2 == color)
-        {
-            self.state[to].push(c);
-        }
+fn solve(
+    mut p: Puzzle,
+    depth: u32,
+    iterations: u32,
+    format: Format,
+    verbosity: Verbosity,
+) -> ExitCode {
+    let mut moves = p.solve(depth, iterations);
+    if format == Format::Json {
+        println!("{}", p.solution_to_json(moves.make_contiguous()));
+    } else if verbosity != Verbosity::Quiet {
+        println!("Initial state:\n{}", format.board(&p));
     }
-
-    fn dfs(&self, depth: u32, score: Score) -> (Score, VecDeque<Move>) {
-        if depth == 0 {
-            return (score, VecDeque::new());
+    let annotated = p
+        .annotate_solution(moves.make_contiguous())
+        .expect("the solver only returns legal moves");
+    for a in annotated {
+        p.do_move(a.step)
+            .expect("the solver only returns legal moves");
+        let mut notes = Vec::new();
+        if let Some(c) = a.completed {
+            notes.push(format!("completes color {c}"));
         }
-
-        // Evaluate all nodes at the given depth
-        let mut best_score = score;
-        let mut best_moves = VecDeque::new();
-        for m in self.moves() {
-            let mut game = self.clone();
-            game.do_move(m);
-            let (child_score, mut moves) = game.dfs(depth - 1, game.rank());
-            if child_score > best_score {
-                best_score = child_score;
-                moves.push_front(m);
-                best_moves = moves;
-
-                if let Score::Win = child_score {
-                    break;
-                }
+        if a.emptied {
+            notes.push(format!("empties column {}", a.step.from));
+        }
+        let notes: String = notes.iter().map(|note| format!(", {note}")).collect();
+        match (format, verbosity) {
+            (Format::Json, _) => {}
+            (_, Verbosity::Quiet) => println!("{}", a.step),
+            (_, Verbosity::Normal) => println!("{}{notes}", a.step),
+            (_, Verbosity::Verbose) => {
+                println!("{}: {:?}{notes}", a.step, a.rank);
+                println!("{}", format.board(&p));
             }
         }
-
-        (best_score, best_moves)
     }
+    if format != Format::Json && verbosity == Verbosity::Normal {
+        println!("Final state:\n{}", format.board(&p));
+    }
+    if p.is_solved() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(NOT_SOLVED)
+    }
+}
 
-    // IDDFS
-    fn solve(&self, max_depth: u32, iterations: u32) -> VecDeque<Move> {
-        let mut all_moves = VecDeque::new();
-        let mut count = 0;
-        let mut game = self.clone();
-        while count < iterations {
-            let mut best_moves = VecDeque::new();
-            for d in 0..max_depth {
-                let (score, moves) = game.dfs(d, game.rank());
-                if let Score::Win = score {
-                    all_moves.extend(moves);
-                    println!("Found a winner in {} moves.", all_moves.len());
-                    return all_moves;
-                }
-                best_moves = moves;
+/// Renders a board for `play`, with the number of each column below it.
+fn play_board(p: &Puzzle) -> String {
+    let board = p.render_terminal(!io::stdout().is_terminal());
+    // Cells are two characters wide with colors, or as wide as the `[c]` cells of the text.
+    let width = if io::stdout().is_terminal() {
+        2
+    } else {
+        let row = board.lines().next().map_or(0, |row| row.chars().count());
+        (row + 1) / p.num_columns().max(1) - 1
+    };
+    let labels: Vec<String> = (0..p.num_columns())
+        .map(|i| format!("{i:^width$}"))
+        .collect();
+    format!("{board}\n{}", labels.join(" ").trim_end())
+}
+
+/// Lets the user solve `p` by typing moves like `0 2`, or `u` to take back the last one.
+fn play(mut p: Puzzle) -> ExitCode {
+    println!("{}", play_board(&p));
+    let mut lines = io::stdin().lock().lines();
+    while !p.is_solved() {
+        print!("move (from to, or u to undo)> ");
+        io::stdout().flush().ok();
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return ExitCode::from(NOT_SOLVED);
+        };
+        let line = line.trim();
+        if line == "u" {
+            if p.undo().is_none() {
+                println!("nothing to undo");
+                continue;
             }
-            for m in &best_moves {
-                game.do_move(*m);
+        } else {
+            let columns: Vec<_> = line.split_whitespace().map(str::parse).collect();
+            let [Ok(from), Ok(to)] = columns[..] else {
+                println!("expected two column numbers, or u to undo");
+                continue;
+            };
+            if let Err(e) = p.do_move(Move { from, to, count: 0 }) {
+                println!("illegal move: {e}");
+                continue;
             }
-            all_moves.extend(best_moves);
-            count += 1;
         }
-        all_moves
+        println!("{}", play_board(&p));
+        if p.is_deadlocked() {
+            println!("no moves left, u to undo");
+        }
     }
+    println!("Solved in {} moves!", p.history().len());
+    ExitCode::SUCCESS
 }
 
-fn main() {
-    let mut p = Puzzle::new(
-        4,
-        &[
-            vec![1, 2, 3, 4],
-            vec![3, 5, 3, 1],
-            vec![6, 1, 2, 5],
-            vec![6, 3, 2, 5],
-            vec![6, 5, 4, 6],
-            vec![2, 1, 4, 4],
-            vec![],
-            vec![],
-        ],
-    );
-    let moves = p.solve(5, 100);
-    println!("Initial state:\n{p}");
-    for m in moves {
-        print!("{m:?} -> ");
-        p.do_move(m);
-        println!("{:?}", p.rank());
-        println!("{p}");
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let verbosity = match (cli.quiet, cli.verbose) {
+        (true, _) => Verbosity::Quiet,
+        (_, true) => Verbosity::Verbose,
+        _ => Verbosity::Normal,
+    };
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(match verbosity {
+            Verbosity::Quiet => log::LevelFilter::Warn,
+            Verbosity::Normal => log::LevelFilter::Info,
+            Verbosity::Verbose => log::LevelFilter::Debug,
+        });
+    }
+    match cli.command {
+        Command::Solve { file } => match read_puzzle(file.as_deref()) {
+            Ok(p) => solve(p, cli.depth, cli.iterations, cli.format, verbosity),
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::from(BAD_INPUT)
+            }
+        },
+        Command::Batch { file } => match read_boards(file.as_deref()) {
+            Ok(boards) => solve_all(&boards, cli.depth, cli.iterations, cli.format),
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::from(BAD_INPUT)
+            }
+        },
+        Command::Play { file } => {
+            let p = match file {
+                Some(path) => match read_puzzle(Some(&path)) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("error: {e}");
+                        return ExitCode::from(BAD_INPUT);
+                    }
+                },
+                None => Puzzle::generate(4, 6, 2, &mut rand::rng()),
+            };
+            play(p)
+        }
+        Command::Validate { file } => match read_puzzle(file.as_deref()) {
+            Ok(p) => {
+                println!(
+                    "valid board with {} columns and {} colors",
+                    p.num_columns(),
+                    p.num_colors()
+                );
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::from(BAD_INPUT)
+            }
+        },
+        Command::Generate {
+            column_size,
+            colors,
+            empty,
+            seed,
+        } => {
+            let p = match seed {
+                Some(seed) => Puzzle::generate_seeded(column_size, colors, empty, seed),
+                None => Puzzle::generate(column_size, colors, empty, &mut rand::rng()),
+            };
+            match cli.format {
+                Format::Json => println!("{}", p.to_json()),
+                format => println!("{}", format.board(&p)),
+            }
+            ExitCode::SUCCESS
+        }
     }
 }
@@ -0,0 +1,64 @@
+use std::hash::Hash;
+
+use crate::{Capacity, Puzzle, PuzzleError};
+
+impl<C: Copy + Eq + Hash> Puzzle<C> {
+    /// Returns the board as a matrix of rows, top row first, each holding a cell per column with
+    /// `None` for the empty slots. There are as many rows as the tallest column has slots.
+    pub fn to_matrix(&self) -> Vec<Vec<Option<C>>> {
+        (0..self.capacity.max())
+            .rev()
+            .map(|idx| self.state.iter().map(|col| col.get(idx).copied()).collect())
+            .collect()
+    }
+
+    /// Builds a puzzle from a matrix shaped like the one returned by [`Puzzle::to_matrix`], with
+    /// columns of the given capacity. Fails if the rows have different lengths, if a ball sits
+    /// above an empty slot, or with the same errors as [`Puzzle::try_new`].
+    pub fn from_matrix(
+        matrix: &[Vec<Option<C>>],
+        capacity: impl Into<Capacity>,
+    ) -> Result<Self, PuzzleError> {
+        let width = matrix.first().map_or(0, Vec::len);
+        if let Some((row, cells)) = matrix.iter().enumerate().find(|(_, r)| r.len() != width) {
+            return Err(PuzzleError::RaggedRow {
+                row,
+                expected: width,
+                found: cells.len(),
+            });
+        }
+
+        // Walk the rows bottom-up so each column is filled from its first slot.
+        let mut columns = vec![Vec::new(); width];
+        for (i, row) in matrix.iter().enumerate().rev() {
+            for (j, &cell) in row.iter().enumerate() {
+                if let Some(c) = cell {
+                    if columns[j].len() != matrix.len() - i - 1 {
+                        return Err(PuzzleError::FloatingBall { row: i, column: j });
+                    }
+                    columns[j].push(c);
+                }
+            }
+        }
+        Puzzle::try_new(capacity, &columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrices_round_trip() {
+        let boards = [
+            crate::tests::main_board(),
+            Puzzle::generate_seeded(3, 4, 2, 65),
+            Puzzle::new(vec![2, 4, 3], &[vec![1, 2], vec![2, 1], vec![]]),
+        ];
+        for p in boards {
+            let q = Puzzle::from_matrix(&p.to_matrix(), p.capacity().clone()).unwrap();
+            assert_eq!(q, p);
+            assert_eq!(q.state, p.state);
+        }
+    }
+}
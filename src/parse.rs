@@ -0,0 +1,172 @@
+use std::str::FromStr;
+
+use crate::{Color, Move, ParsePuzzleError, Puzzle, PuzzleError};
+
+/// Splits a row like `[1] [ ] [3]` into the contents of its cells. Blanks as wide as a cell,
+/// printed above the top of shorter columns, are returned as `None`.
+fn parse_row(line: &str) -> Option<Vec<Option<&str>>> {
+    let start = line.find('[')?;
+    let width = line[start..].find(']')? + 1;
+    let blank = " ".repeat(width);
+    let mut cells = Vec::new();
+    let mut rest = line;
+    loop {
+        if let Some(after) = rest.strip_prefix(blank.as_str()) {
+            cells.push(None);
+            rest = after;
+        } else {
+            let inner = rest.strip_prefix('[')?;
+            let end = inner.find(']')?;
+            cells.push(Some(&inner[..end]));
+            rest = &inner[end + 1..];
+        }
+        if rest.is_empty() {
+            return Some(cells);
+        }
+        rest = rest.strip_prefix(' ')?;
+    }
+}
+
+/// Parses the content of a single cell, `None` meaning an empty slot. This is the inverse of
+/// `color_token`, tolerating the padding added by `Display`.
+fn parse_cell(cell: &str) -> Result<Option<Color>, ()> {
+    let token = cell.trim_start_matches(' ');
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) if !cell.is_empty() => Ok(None),
+        (Some(c), None) => c.to_digit(36).map(Some).ok_or(()),
+        (Some(_), Some(_)) if token.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Color::from_str_radix(token, 16).map(Some).map_err(|_| ())
+        }
+        _ => Err(()),
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = ParsePuzzleError;
+
+    /// Parses the grid produced by `Display`: one row per line, top row first, with empty slots
+    /// rendered as blanks. The column size is the number of rows, unless some columns are
+    /// shorter, in which case each column gets its own size.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let cells = parse_row(line).ok_or(ParsePuzzleError::Malformed { row: i })?;
+            if let Some(first) = rows.first().map(Vec::len)
+                && first != cells.len()
+            {
+                return Err(ParsePuzzleError::Ragged {
+                    row: i,
+                    expected: first,
+                    found: cells.len(),
+                });
+            }
+            let cells = cells
+                .iter()
+                .enumerate()
+                .map(|(j, cell)| {
+                    cell.map(parse_cell)
+                        .transpose()
+                        .map_err(|()| ParsePuzzleError::InvalidColor { row: i, column: j })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(cells);
+        }
+        if rows.is_empty() {
+            return Err(ParsePuzzleError::Empty);
+        }
+
+        // Walk the rows bottom-up so each column is filled from its first slot.
+        let mut columns = vec![Vec::new(); rows[0].len()];
+        let mut sizes = vec![0; rows[0].len()];
+        for (i, row) in rows.iter().enumerate().rev() {
+            for (j, &cell) in row.iter().enumerate() {
+                let Some(cell) = cell else { continue };
+                if sizes[j] != rows.len() - i - 1 {
+                    return Err(ParsePuzzleError::MissingSlot { row: i, column: j });
+                }
+                sizes[j] += 1;
+                if let Some(c) = cell {
+                    if columns[j].len() != rows.len() - i - 1 {
+                        return Err(ParsePuzzleError::Floating { row: i, column: j });
+                    }
+                    columns[j].push(c);
+                }
+            }
+        }
+
+        if sizes.iter().all(|&size| size == rows.len()) {
+            Ok(Puzzle::new(rows.len(), &columns))
+        } else {
+            Ok(Puzzle::new(sizes, &columns))
+        }
+    }
+}
+
+/// Parses a single move like `0->6` or `0 -> 6 (×2)`, the inverse of the `Display` of [`Move`].
+fn parse_move(s: &str) -> Option<Move> {
+    let (from, rest) = s.split_once("->")?;
+    let (to, count) = match rest.split_once('(') {
+        Some((to, count)) => {
+            let count = count.trim_end().strip_suffix(')')?.strip_prefix('×')?;
+            (to, count.parse().ok()?)
+        }
+        None => (rest, 0),
+    };
+    Some(Move {
+        from: from.trim().parse().ok()?,
+        to: to.trim().parse().ok()?,
+        count,
+    })
+}
+
+/// Parses a comma-separated list of moves like `0->6, 1->7, 4->6`, in the notation of the
+/// `Display` of [`Move`], with or without spaces and numbers of balls. The columns are only
+/// checked when the moves are played, since the board isn't known here, and
+/// [`Puzzle::do_move`] works out the number of balls anyway.
+pub fn parse_moves(s: &str) -> Result<Vec<Move>, PuzzleError> {
+    if s.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    s.split(',')
+        .enumerate()
+        .map(|(index, m)| parse_move(m).ok_or(PuzzleError::InvalidMoveNotation { index }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsed_moves_solve_the_board() {
+        let mut p = Puzzle::new(2, &[vec![1, 2], vec![1, 2], vec![]]);
+        let moves = parse_moves("0->2, 1->2,0 -> 1").unwrap();
+        p.apply_moves(&moves).unwrap();
+        assert!(p.is_solved());
+
+        let written: Vec<String> = p.history().iter().map(Move::to_string).collect();
+        assert_eq!(parse_moves(&written.join(", ")).unwrap(), p.history());
+        assert_eq!(
+            parse_moves("0->2, 1-2"),
+            Err(PuzzleError::InvalidMoveNotation { index: 1 })
+        );
+    }
+
+    #[test]
+    fn printed_boards_parse_back() {
+        let p = crate::tests::main_board();
+        assert_eq!(p.to_string().parse::<Puzzle>(), Ok(p));
+
+        let sized = Puzzle::new(vec![4, 2, 3, 1], &[vec![1, 2, 1], vec![2], vec![], vec![1]]);
+        assert_eq!(sized.to_string().parse::<Puzzle>(), Ok(sized));
+
+        // The joker isn't printed, only its balls, so it has to be set again.
+        let mut joker = Puzzle::new(3, &[vec![1, 9, 2], vec![2, 1, 9], vec![]]);
+        joker.set_joker(Some(9));
+        let mut parsed: Puzzle = joker.to_string().parse().unwrap();
+        assert_ne!(parsed, joker);
+        parsed.set_joker(Some(9));
+        assert_eq!(parsed, joker);
+    }
+}
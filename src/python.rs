@@ -0,0 +1,45 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::Puzzle;
+
+/// Python class wrapping a [`Puzzle`].
+#[pyclass(name = "Puzzle")]
+struct PyPuzzle(Puzzle);
+
+#[pymethods]
+impl PyPuzzle {
+    /// Builds a puzzle from its columns, listed bottom to top, raising `ValueError` if a column
+    /// holds more than `column_size` balls.
+    #[new]
+    fn new(columns: Vec<Vec<u32>>, column_size: usize) -> PyResult<Self> {
+        Puzzle::try_new(column_size, &columns)
+            .map(PyPuzzle)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Solves the puzzle with [`Puzzle::solve`] and returns the moves as `(from, to)` tuples,
+    /// which may not win if the solver runs out of iterations.
+    #[pyo3(signature = (max_depth = 5, iterations = 100))]
+    fn solve(&self, max_depth: u32, iterations: u32) -> Vec<(usize, usize)> {
+        self.0
+            .solve(max_depth, iterations)
+            .into_iter()
+            .map(|m| (m.from, m.to))
+            .collect()
+    }
+
+    fn is_solved(&self) -> bool {
+        self.0.is_solved()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// Module imported from Python as `sorting`.
+#[pymodule]
+fn sorting(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPuzzle>()
+}
@@ -0,0 +1,128 @@
+use std::hash::Hash;
+
+use crate::{Color, Puzzle, Score};
+
+/// Weights of the features scored by [`Puzzle::rank_with`]. The column weights are multiplied by
+/// the number of columns, so that the state of the columns dominates over the number of moves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RankWeights {
+    /// Column with all the balls of a single color.
+    pub complete_column: usize,
+    /// Column with balls of a single color, but not all of them.
+    pub single_color_column: usize,
+    pub empty_column: usize,
+    /// Move available on the board.
+    pub per_move: usize,
+    /// Penalty for each ball of [`Puzzle::buried_count`], none by default.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub buried_ball: usize,
+}
+
+/// Evaluation of boards guiding the searches, ordered so that better boards score greater.
+/// Solved boards are expected to score [`Score::Win`].
+pub trait Heuristic<C = Color> {
+    fn score(&self, p: &Puzzle<C>) -> Score;
+}
+
+/// The evaluation of [`Puzzle::rank`], rewarding complete, single-colored and empty columns, as
+/// well as boards with many moves available.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DefaultRank;
+
+impl<C: Copy + Eq + Hash> Heuristic<C> for DefaultRank {
+    fn score(&self, p: &Puzzle<C>) -> Score {
+        p.rank_with(&RankWeights::default())
+    }
+}
+
+impl<C: Copy + Eq + Hash> Heuristic<C> for RankWeights {
+    fn score(&self, p: &Puzzle<C>) -> Score {
+        p.rank_with(self)
+    }
+}
+
+impl Default for RankWeights {
+    /// The weights used by [`Puzzle::rank`].
+    fn default() -> Self {
+        RankWeights {
+            complete_column: 1000,
+            single_color_column: 100,
+            empty_column: 10,
+            per_move: 1,
+            buried_ball: 0,
+        }
+    }
+}
+
+impl<C: Copy + Eq + Hash> Puzzle<C> {
+    /// Scores the board heuristically with the given weights, returning [`Score::Win`] once it
+    /// is solved.
+    pub fn rank_with(&self, w: &RankWeights) -> Score {
+        if self.is_solved() {
+            return Score::Win;
+        }
+
+        let mut score: usize = 0;
+        for (i, col) in self.state.iter().enumerate() {
+            // Adding the number of moves to the score to promote states that are not stuck.
+            score += w.per_move * self.column_moves(i).count();
+            // We use self.state.len() as a multiplier to ensure the various conditions below
+            // (empty columns, columns with just one color, columns fully sorted with all the
+            // entries of that color) dominate over just being able to move items.
+            if col.is_empty() {
+                // Empty column
+                score += w.empty_column * self.state.len();
+            } else if self.single_color(col).is_some() {
+                // Column containing just a single color, jokers aside
+                if self.is_complete(i) {
+                    // Column with all the entries of a single color
+                    score += w.complete_column * self.state.len();
+                } else {
+                    score += w.single_color_column * self.state.len();
+                }
+            } else if col.iter().all(|&c| self.is_joker(c)) {
+                score += w.single_color_column * self.state.len();
+            }
+        }
+
+        if w.buried_ball > 0 {
+            score = score.saturating_sub(w.buried_ball * self.buried_count());
+        }
+        Score::Score(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_scale_each_feature() {
+        // A complete column, two single-colored ones and the two moves between them.
+        let p = Puzzle::new(2, &[vec![1, 1], vec![2], vec![2]]);
+        assert_eq!(p.rank_with(&RankWeights::default()), Score::Score(3602));
+        let moves_only = RankWeights {
+            complete_column: 0,
+            single_color_column: 0,
+            empty_column: 0,
+            per_move: 5,
+            buried_ball: 0,
+        };
+        assert_eq!(p.rank_with(&moves_only), Score::Score(10));
+
+        // The 1 and the 3 lie under other colors.
+        let buried = Puzzle::new(2, &[vec![1, 2], vec![3, 2], vec![]]);
+        let penalized = RankWeights {
+            buried_ball: 7,
+            ..RankWeights::default()
+        };
+        let Score::Score(plain) = buried.rank_with(&RankWeights::default()) else {
+            panic!("the board isn't solved");
+        };
+        assert_eq!(buried.rank_with(&penalized), Score::Score(plain - 14));
+
+        let solved = Puzzle::new(2, &[vec![1, 1], vec![]]);
+        assert_eq!(solved.rank_with(&moves_only), Score::Win);
+    }
+}
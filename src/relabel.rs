@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::zobrist::ZobristHash;
+use crate::{Capacity, Color, Column, Puzzle, recount};
+
+/// Column of a relabeled board along with its size, in the order columns are compared in.
+type Relabeled = (usize, Column<Color>);
+
+/// Search of [`Puzzle::relabel_canonical`]: columns are picked one at a time, their new colors
+/// being numbered in order of appearance, keeping the order giving the smallest board.
+struct Relabeling<'a, C> {
+    puzzle: &'a Puzzle<C>,
+    /// Ids given to the colors of the columns picked so far, and to the joker.
+    ids: HashMap<C, Color>,
+    next_id: Color,
+    /// Columns not picked yet.
+    remaining: Vec<usize>,
+    picked: Vec<Relabeled>,
+    best: Option<Vec<Relabeled>>,
+}
+
+impl<C: Copy + Eq + Hash> Relabeling<'_, C> {
+    /// Returns column `i` with its colors renamed, the ones without an id yet taking the next
+    /// ones in order of appearance.
+    fn relabel(&self, i: usize) -> Relabeled {
+        let mut new = Vec::new();
+        let col = self.puzzle.state[i]
+            .iter()
+            .map(|c| match self.ids.get(c) {
+                Some(&id) => id,
+                None => {
+                    let pos = new.iter().position(|n| n == c).unwrap_or_else(|| {
+                        new.push(*c);
+                        new.len() - 1
+                    });
+                    self.next_id + pos as Color
+                }
+            })
+            .collect();
+        (self.puzzle.capacity.of(i), col)
+    }
+
+    /// Whether all the balls of the colors of column `i` are in it, none of them having an id
+    /// yet. Picking any of two such columns relabeled the same leads to the same board.
+    fn self_contained(&self, i: usize) -> bool {
+        let col = &self.puzzle.state[i];
+        col.iter().all(|c| {
+            self.puzzle.is_joker(*c)
+                || (!self.ids.contains_key(c)
+                    && col.iter().filter(|&b| b == c).count() == self.puzzle.colors_count[c])
+        })
+    }
+
+    fn search(&mut self) {
+        if self.remaining.is_empty() {
+            if self.best.as_ref().is_none_or(|best| self.picked < *best) {
+                self.best = Some(self.picked.clone());
+            }
+            return;
+        }
+
+        let candidates: Vec<(usize, Relabeled)> = self
+            .remaining
+            .iter()
+            .map(|&i| (i, self.relabel(i)))
+            .collect();
+        let min = candidates.iter().map(|(_, col)| col).min().unwrap().clone();
+        if let Some(best) = &self.best {
+            let depth = self.picked.len();
+            if (&self.picked[..], &min) > (&best[..depth], &best[depth]) {
+                return;
+            }
+        }
+
+        let mut tried: Vec<usize> = Vec::new();
+        for (i, col) in candidates {
+            if col != min {
+                continue;
+            }
+            // Identical columns, or columns holding colors found nowhere else, are
+            // interchangeable: only the first one needs to be searched.
+            let same = tried.iter().any(|&j| {
+                self.puzzle.state[j] == self.puzzle.state[i]
+                    || (self.self_contained(j) && self.self_contained(i))
+            });
+            if same {
+                continue;
+            }
+            tried.push(i);
+
+            let mut added = Vec::new();
+            for &c in &self.puzzle.state[i] {
+                if !self.ids.contains_key(&c) {
+                    self.ids.insert(c, self.next_id);
+                    self.next_id += 1;
+                    added.push(c);
+                }
+            }
+            let pos = self.remaining.iter().position(|&r| r == i).unwrap();
+            self.remaining.remove(pos);
+            self.picked.push(col);
+
+            self.search();
+
+            self.picked.pop();
+            self.remaining.insert(pos, i);
+            for c in added {
+                self.ids.remove(&c);
+                self.next_id -= 1;
+            }
+        }
+    }
+}
+
+impl<C: Copy + Eq + Hash> Puzzle<C> {
+    /// Returns the board with its colors renamed so that boards only differing by the ids of
+    /// their colors and by the order of their columns give the same board. Colors are numbered
+    /// from 0 in order of appearance, from the bottom of each column, over the order of the
+    /// columns giving the smallest board, which is the order the columns are returned in. The
+    /// joker, if any, is numbered after the other colors. The history is dropped.
+    pub fn relabel_canonical(&self) -> Puzzle {
+        let joker = self.joker.map(|_| self.colors_count.len() as Color);
+        let mut search = Relabeling {
+            puzzle: self,
+            ids: self.joker.into_iter().zip(joker).collect(),
+            next_id: 0,
+            remaining: (0..self.state.len()).collect(),
+            picked: Vec::new(),
+            best: None,
+        };
+        search.search();
+
+        let (sizes, state): (Vec<usize>, Vec<Column<Color>>) =
+            search.best.unwrap_or_default().into_iter().unzip();
+        let capacity = match self.capacity {
+            Capacity::Uniform(size) => Capacity::Uniform(size),
+            Capacity::PerColumn(_) => Capacity::PerColumn(sizes),
+        };
+        Puzzle {
+            zobrist: ZobristHash::new(&state, &capacity),
+            capacity,
+            colors_count: recount(&state, joker),
+            state,
+            joker,
+            move_mode: self.move_mode,
+            history: Vec::new(),
+            lock_complete: self.lock_complete,
+        }
+    }
+
+    /// Returns whether the two boards are the same up to the order of their columns and the ids
+    /// of their colors, e.g. to leave out generated boards that were already seen.
+    pub fn equivalent_to(&self, other: &Puzzle<C>) -> bool {
+        self.relabel_canonical() == other.relabel_canonical()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_permuted_boards_relabel_the_same() {
+        let p = Puzzle::new(
+            4,
+            &[vec![1, 2, 2, 3], vec![3, 1, 1, 2], vec![2, 3, 3, 1], vec![]],
+        );
+        // 1, 2 and 3 renamed to 7, 9 and 8, with the columns shuffled.
+        let q = Puzzle::new(
+            4,
+            &[vec![], vec![7, 9, 9, 8], vec![9, 8, 8, 7], vec![8, 7, 7, 9]],
+        );
+        let (a, b) = (p.relabel_canonical(), q.relabel_canonical());
+        assert_eq!(a.state, b.state);
+        assert_eq!(a.colors().collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(a.relabel_canonical().state, a.state);
+    }
+
+    #[test]
+    fn tells_equivalent_boards() {
+        let p = Puzzle::new(
+            4,
+            &[vec![1, 2, 2, 3], vec![3, 1, 1, 2], vec![2, 3, 3, 1], vec![]],
+        );
+        let q = Puzzle::new(
+            4,
+            &[vec![], vec![7, 9, 9, 8], vec![9, 8, 8, 7], vec![8, 7, 7, 9]],
+        );
+        assert!(p.equivalent_to(&q));
+        assert!(q.equivalent_to(&p));
+        assert!(p.equivalent_to(&p));
+
+        // The same balls, but two of them swapped in the last column.
+        let near = Puzzle::new(
+            4,
+            &[vec![], vec![7, 9, 9, 8], vec![9, 8, 8, 7], vec![8, 7, 9, 7]],
+        );
+        assert!(!p.equivalent_to(&near));
+        assert!(!near.equivalent_to(&p));
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt::Write;
+
+use crate::zobrist::splitmix64;
+use crate::{Color, Puzzle, color_token};
+
+/// Background colors for the first colors ids, picked to be easy to tell apart.
+const PALETTE: [(u8, u8, u8); 20] = [
+    (230, 25, 75),
+    (60, 180, 75),
+    (255, 225, 25),
+    (0, 130, 200),
+    (245, 130, 48),
+    (145, 30, 180),
+    (70, 240, 240),
+    (240, 50, 230),
+    (210, 245, 60),
+    (250, 190, 212),
+    (0, 128, 128),
+    (220, 190, 255),
+    (170, 110, 40),
+    (255, 250, 200),
+    (128, 0, 0),
+    (170, 255, 195),
+    (128, 128, 0),
+    (255, 215, 180),
+    (0, 0, 128),
+    (128, 128, 128),
+];
+
+/// Background of an empty slot, dark so that balls stand out while the columns still show their
+/// size.
+const EMPTY_RGB: (u8, u8, u8) = (48, 48, 48);
+
+/// Emojis for the first color ids, circles first and then squares.
+const EMOJIS: [&str; 16] = [
+    "🔴", "🟢", "🔵", "🟡", "🟣", "🟤", "⚫", "⚪", "🟠", "🟥", "🟩", "🟦", "🟨", "🟪", "🟫", "🟧",
+];
+
+/// Emoji standing for an empty slot.
+const EMPTY_EMOJI: &str = "⬛";
+
+/// Returns the RGB value used to draw a color: an entry of the palette when there is one, or a
+/// value derived from a hash of the id otherwise.
+fn rgb(c: Color) -> (u8, u8, u8) {
+    match PALETTE.get(c as usize) {
+        Some(&rgb) => rgb,
+        None => {
+            let [r, g, b, ..] = splitmix64(c.into()).to_le_bytes();
+            (r, g, b)
+        }
+    }
+}
+
+impl Puzzle {
+    /// Renders the board for a terminal supporting 24-bit colors, with each ball drawn as a block
+    /// of its color and each empty slot as a dark one. The layout is the same as `Display`, the
+    /// top of the columns being printed first and nothing above the top of shorter columns.
+    pub fn render_ansi(&self) -> String {
+        let mut out = String::new();
+        let height = self.capacity.max();
+        for i in 0..height {
+            if i > 0 {
+                out.push('\n');
+            }
+            let idx = height - i - 1;
+            for (j, col) in self.state.iter().enumerate() {
+                if j > 0 {
+                    out.push(' ');
+                }
+                if idx >= self.capacity.of(j) {
+                    out.push_str("  ");
+                    continue;
+                }
+                let (r, g, b) = col.get(idx).map_or(EMPTY_RGB, |&c| rgb(c));
+                write!(out, "\x1b[48;2;{r};{g};{b}m  \x1b[0m").unwrap();
+            }
+        }
+        out
+    }
+
+    /// Renders the board with [`Puzzle::render_ansi`], or as plain text like `Display` when
+    /// `no_color` is set, e.g. because the output is not a terminal.
+    pub fn render_terminal(&self, no_color: bool) -> String {
+        if no_color {
+            self.to_string()
+        } else {
+            self.render_ansi()
+        }
+    }
+
+    /// Renders the board with emojis, e.g. to share it in a chat, laid out like `Display` with
+    /// the top of the columns first. Each of the first 16 colors gets its own emoji, and empty
+    /// slots are drawn as ⬛. Colors beyond those are printed as their `Display` token, padded to
+    /// the width of an emoji.
+    pub fn render_emoji(&self) -> String {
+        let height = self.capacity.max();
+        let mut out = String::new();
+        for i in 0..height {
+            if i > 0 {
+                out.push('\n');
+            }
+            let idx = height - i - 1;
+            for (j, col) in self.state.iter().enumerate() {
+                if idx >= self.capacity.of(j) {
+                    out.push_str("  ");
+                    continue;
+                }
+                match col.get(idx) {
+                    Some(&c) => match EMOJIS.get(c as usize) {
+                        Some(emoji) => out.push_str(emoji),
+                        None => write!(out, "{:>2}", color_token(c)).unwrap(),
+                    },
+                    None => out.push_str(EMPTY_EMOJI),
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emoji_renders_differ_by_a_ball() {
+        let a = Puzzle::new(2, &[vec![0, 1], vec![1, 0], vec![]]);
+        let b = Puzzle::new(2, &[vec![0, 1], vec![1, 2], vec![]]);
+        assert_ne!(a.render_emoji(), b.render_emoji());
+        assert_eq!(a.render_emoji().lines().count(), 2);
+    }
+
+    #[test]
+    fn ansi_renders_leave_blank_above_shorter_columns() {
+        let p = Puzzle::new(vec![2, 1], &[vec![0], vec![]]);
+        let ball = "\x1b[48;2;230;25;75m  \x1b[0m";
+        let empty = "\x1b[48;2;48;48;48m  \x1b[0m";
+        assert_eq!(p.render_ansi(), format!("{empty}   \n{ball} {empty}"));
+    }
+}
@@ -0,0 +1,99 @@
+mod astar;
+mod batch;
+mod beam;
+mod bfs;
+mod enumerate;
+mod greedy;
+mod ida;
+mod parallel;
+#[cfg(feature = "rayon")]
+mod pool;
+mod rollout;
+
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use crate::{Move, Puzzle};
+
+/// Rebuilds the moves leading to `node` in a search tree where each entry holds the index of its
+/// parent and the move played from it, the root being the only entry without a parent.
+fn backtrack(tree: &[Option<(usize, Move)>], mut node: usize) -> VecDeque<Move> {
+    let mut moves = VecDeque::new();
+    while let Some((parent, m)) = tree[node] {
+        moves.push_front(m);
+        node = parent;
+    }
+    moves
+}
+
+/// Lower bound on the number of moves left: the number of runs of same-colored balls across all
+/// columns, minus the number of colors, jokers being left out.
+///
+/// A solved board has exactly one run per color, so it scores zero. A pour lifts the top run of
+/// a column and drops it on an empty column or on a run of the same color. The source loses at
+/// most that one run, while the destination either merges it into its top run or gains a new
+/// one, so a single move can remove at most one run. This means the heuristic never
+/// overestimates the remaining moves, which is what makes A* return optimal solutions. It is
+/// also consistent, so a board never needs to be expanded twice.
+fn heuristic<C: Copy + Eq + Hash>(p: &Puzzle<C>) -> usize {
+    p.color_groups() - p.colors_count.len()
+}
+
+impl<C: Copy + Eq + Hash> Puzzle<C> {
+    /// Returns the number of runs of same-colored balls across all columns, which is the number
+    /// of non-empty columns on a solved board. Jokers are left out, so they neither count as
+    /// runs nor split the run of a color.
+    pub fn color_groups(&self) -> usize {
+        if self.joker.is_none() {
+            return self
+                .state
+                .iter()
+                .map(|col| col.chunk_by(|a, b| a == b).count())
+                .sum();
+        }
+        self.state
+            .iter()
+            .map(|col| {
+                let mut balls: Vec<C> =
+                    col.iter().copied().filter(|&c| !self.is_joker(c)).collect();
+                balls.dedup();
+                balls.len()
+            })
+            .sum()
+    }
+
+    /// Returns a number of moves the puzzle can't be solved in less than: the number of runs of
+    /// same-colored balls across all columns from [`Puzzle::color_groups`], minus the number of
+    /// colors. A solved board has a single run per color, and a pour removes at most one run,
+    /// since it lifts a single run and either merges it with the run it lands on or keeps it
+    /// whole.
+    pub fn min_moves_lower_bound(&self) -> usize {
+        heuristic(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lower_bound_never_exceeds_the_shortest_solution() {
+        for (seed, p, shortest) in crate::tests::small_boards() {
+            assert!(p.min_moves_lower_bound() <= shortest, "seed {seed}");
+        }
+        let solved = Puzzle::new(2, &[vec![1, 1], vec![2, 2], vec![]]);
+        assert_eq!(solved.min_moves_lower_bound(), 0);
+    }
+
+    #[test]
+    fn counts_color_groups() {
+        let solved = Puzzle::new(3, &[vec![1, 1, 1], vec![2, 2, 2], vec![], vec![3, 3, 3]]);
+        assert_eq!(solved.color_groups(), 3);
+        let scrambled = Puzzle::new(
+            3,
+            &[vec![1, 2, 1], vec![2, 1, 2], vec![3, 3], vec![2, 1, 3]],
+        );
+        assert_eq!(scrambled.color_groups(), 10);
+        assert_eq!(scrambled.min_moves_lower_bound(), 7);
+    }
+}
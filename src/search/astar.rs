@@ -0,0 +1,85 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use super::{backtrack, heuristic};
+use crate::{Move, Puzzle, SearchColor};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Finds a shortest solution with an A* search guided by an admissible heuristic, or returns
+    /// `None` if the puzzle cannot be solved. This usually explores far fewer boards than
+    /// [`Puzzle::solve_bfs`].
+    pub fn solve_astar(&self) -> Option<VecDeque<Move>> {
+        self.astar(usize::MAX)
+    }
+
+    /// Finds a shortest solution if there is one of at most `max_moves` moves, or returns `None`
+    /// otherwise, even if the puzzle can be solved with more moves. Boards that can't be solved
+    /// within the bound, according to the heuristic, are never explored.
+    pub fn solve_within(&self, max_moves: usize) -> Option<VecDeque<Move>> {
+        self.astar(max_moves)
+    }
+
+    /// A* search ignoring the boards that can't be solved within `max_moves`.
+    fn astar(&self, max_moves: usize) -> Option<VecDeque<Move>> {
+        if heuristic(self) > max_moves {
+            return None;
+        }
+        let mut boards = vec![self.without_history()];
+        let mut tree = vec![None];
+        // Cost of the cheapest known path to each board.
+        let mut best = HashMap::from([(self.canonical_hash(), 0)]);
+        let mut heap = BinaryHeap::from([Reverse((heuristic(self), 0, 0))]);
+
+        while let Some(Reverse((_, g, node))) = heap.pop() {
+            let game = boards[node].clone();
+            if best[&game.canonical_hash()] < g {
+                // A cheaper path to this board was found after it was queued.
+                continue;
+            }
+            if game.is_solved() {
+                return Some(backtrack(&tree, node));
+            }
+
+            for m in game.moves() {
+                let mut next = game.clone();
+                next.pour(m);
+                let key = next.canonical_hash();
+                if best.get(&key).is_some_and(|&cost| cost <= g + 1) {
+                    continue;
+                }
+                best.insert(key, g + 1);
+                let f = g + 1 + heuristic(&next);
+                if f > max_moves {
+                    continue;
+                }
+                boards.push(next);
+                tree.push(Some((node, m)));
+                heap.push(Reverse((f, g + 1, boards.len() - 1)));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::small_boards;
+
+    #[test]
+    fn astar_is_as_short_as_bfs() {
+        for (seed, p, shortest) in small_boards() {
+            let astar = p.solve_astar().unwrap();
+            assert_eq!(astar.len(), shortest, "seed {seed}");
+            assert_eq!(p.verify_solution(&Vec::from(astar)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn bounded_searches_need_room_for_the_shortest_solution() {
+        for (seed, p, shortest) in small_boards() {
+            assert_eq!(p.solve_within(shortest - 1), None, "seed {seed}");
+            let moves = p.solve_within(shortest).unwrap();
+            assert_eq!(moves.len(), shortest, "seed {seed}");
+        }
+    }
+}
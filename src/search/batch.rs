@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use std::num::NonZero;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::{Move, Puzzle, SearchColor};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Solves each of `inputs` with [`Puzzle::solve`], returning the winning moves of each
+    /// board in order, or `None` for the boards no win was found for.
+    pub fn solve_batch(
+        inputs: &[Puzzle<C>],
+        max_depth: u32,
+        iterations: u32,
+    ) -> Vec<Option<VecDeque<Move>>> {
+        inputs
+            .iter()
+            .map(|p| p.solve_won(max_depth, iterations))
+            .collect()
+    }
+
+    /// Same as [`Puzzle::solve_batch`], with the boards solved by a pool of threads, up to the
+    /// available parallelism. The results are the same, in the same order.
+    pub fn solve_batch_parallel(
+        inputs: &[Puzzle<C>],
+        max_depth: u32,
+        iterations: u32,
+    ) -> Vec<Option<VecDeque<Move>>> {
+        let threads = thread::available_parallelism()
+            .map_or(1, NonZero::get)
+            .min(inputs.len());
+        let next = AtomicUsize::new(0);
+        let mut results = vec![None; inputs.len()];
+        thread::scope(|s| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(p) = inputs.get(i) else {
+                                return done;
+                            };
+                            done.push((i, p.solve_won(max_depth, iterations)));
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                for (i, result) in worker.join().expect("solver thread panicked") {
+                    results[i] = result;
+                }
+            }
+        });
+        results
+    }
+
+    /// Same as [`Puzzle::solve`], returning `None` if the moves don't win.
+    fn solve_won(&self, max_depth: u32, iterations: u32) -> Option<VecDeque<Move>> {
+        let moves = self.solve(max_depth, iterations);
+        let mut game = self.without_history();
+        for &m in &moves {
+            game.pour(m);
+        }
+        game.is_solved().then_some(moves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_report_unsolvable_boards() {
+        let boards = [
+            crate::tests::main_board(),
+            Puzzle::new(2, &[vec![1, 2], vec![2, 1]]),
+            Puzzle::generate_seeded(3, 4, 2, 88),
+        ];
+        let results = Puzzle::solve_batch(&boards, 5, 100);
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_none());
+        for i in [0, 2] {
+            let moves = Vec::from(results[i].clone().unwrap());
+            assert_eq!(boards[i].verify_solution(&moves), Ok(()));
+        }
+    }
+
+    #[test]
+    fn parallel_batches_match_sequential_ones() {
+        let mut boards: Vec<Puzzle> = (0..6)
+            .map(|seed| Puzzle::generate_seeded(3, 4, 2, seed))
+            .collect();
+        boards.push(Puzzle::new(2, &[vec![1, 2], vec![2, 1]]));
+        assert_eq!(
+            Puzzle::solve_batch_parallel(&boards, 5, 100),
+            Puzzle::solve_batch(&boards, 5, 100)
+        );
+        assert!(Puzzle::solve_batch_parallel(&boards[..0], 5, 100).is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+
+use super::backtrack;
+use crate::{Move, Puzzle, SearchColor};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Finds a solution with a beam search: the boards reachable in one more move are ranked and
+    /// only the best `width` ones are expanded further, until one of them wins or none is left.
+    /// A width of 1 follows the best move greedily, while larger widths get closer to the
+    /// shortest solutions of [`Puzzle::solve_bfs`] at the cost of exploring more boards. Returns
+    /// `None` if every board of the beam runs out of new moves.
+    pub fn solve_beam(&self, width: usize) -> Option<VecDeque<Move>> {
+        if self.is_solved() {
+            return Some(VecDeque::new());
+        }
+
+        let mut visited = HashSet::from([self.canonical_hash()]);
+        let mut tree = vec![None];
+        let mut beam = vec![(self.without_history(), 0)];
+        while !beam.is_empty() {
+            let mut candidates = Vec::new();
+            for (game, node) in &beam {
+                for m in game.moves() {
+                    let mut next = game.clone();
+                    next.pour(m);
+                    if !visited.insert(next.canonical_hash()) {
+                        continue;
+                    }
+                    tree.push(Some((*node, m)));
+                    if next.is_solved() {
+                        return Some(backtrack(&tree, tree.len() - 1));
+                    }
+                    candidates.push((next.rank(), next, tree.len() - 1));
+                }
+            }
+            // The sort is stable, so ties are broken by move order and the search is
+            // deterministic.
+            candidates.sort_by_key(|&(score, _, _)| Reverse(score));
+            candidates.truncate(width.max(1));
+            beam = candidates
+                .into_iter()
+                .map(|(_, game, node)| (game, node))
+                .collect();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{main_board, small_boards};
+
+    #[test]
+    fn unbounded_beams_are_as_short_as_bfs() {
+        for (seed, p, shortest) in small_boards() {
+            let beam = p.solve_beam(usize::MAX).unwrap();
+            assert_eq!(beam.len(), shortest, "seed {seed}");
+            assert_eq!(p.verify_solution(&Vec::from(beam)), Ok(()));
+        }
+        let p = main_board();
+        let greedy = p.solve_beam(1).unwrap();
+        assert_eq!(p.verify_solution(&Vec::from(greedy)), Ok(()));
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::backtrack;
+use crate::{Move, Puzzle, Score, SearchColor};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Finds a shortest solution with a breadth-first search, or returns `None` if the puzzle
+    /// cannot be solved. Boards that only differ by the order of their columns are explored once.
+    pub fn solve_bfs(&self) -> Option<VecDeque<Move>> {
+        if self.is_solved() {
+            return Some(VecDeque::new());
+        }
+
+        let mut visited = HashSet::from([self.canonical_hash()]);
+        let mut tree = vec![None];
+        let mut queue = VecDeque::from([(self.without_history(), 0)]);
+        while let Some((game, node)) = queue.pop_front() {
+            for m in game.moves() {
+                let mut next = game.clone();
+                next.pour(m);
+                if !visited.insert(next.canonical_hash()) {
+                    continue;
+                }
+                tree.push(Some((node, m)));
+                if next.is_solved() {
+                    return Some(backtrack(&tree, tree.len() - 1));
+                }
+                queue.push_back((next, tree.len() - 1));
+            }
+        }
+        None
+    }
+
+    /// Returns a shortest winning line along with [`Score::Win`] if the puzzle can be solved.
+    /// Otherwise, returns the moves leading to the best ranked board that can be reached, with
+    /// its score, which helps telling how close an unsolvable board gets to a win. Both come out
+    /// of a single breadth-first search, which visits every reachable board when there is no
+    /// win, so this is only practical for small puzzles. A deadlocked board, where no progress is
+    /// possible at all, scores [`Score::Deadlocked`].
+    pub fn best_effort(&self) -> (VecDeque<Move>, Score) {
+        if self.is_solved() {
+            return (VecDeque::new(), Score::Win);
+        }
+        if self.is_deadlocked() {
+            return (VecDeque::new(), Score::Deadlocked);
+        }
+
+        let mut visited = HashSet::from([self.canonical_hash()]);
+        let mut tree = vec![None];
+        let mut best = (self.rank(), 0);
+        let mut queue = VecDeque::from([(self.without_history(), 0)]);
+        while let Some((game, node)) = queue.pop_front() {
+            for m in game.moves() {
+                let mut next = game.clone();
+                next.pour(m);
+                if !visited.insert(next.canonical_hash()) {
+                    continue;
+                }
+                tree.push(Some((node, m)));
+                if next.is_solved() {
+                    return (backtrack(&tree, tree.len() - 1), Score::Win);
+                }
+                let score = next.rank();
+                if score > best.0 {
+                    best = (score, tree.len() - 1);
+                }
+                queue.push_back((next, tree.len() - 1));
+            }
+        }
+        (backtrack(&tree, best.1), best.0)
+    }
+
+    /// Counts the distinct boards reachable from this one, itself included, boards only
+    /// differing by the order of their columns being counted once. The count stops at `limit`.
+    /// The number of boards grows very quickly with the size of the puzzle, so this is only
+    /// practical for small ones.
+    pub fn count_reachable_states(&self, limit: usize) -> usize {
+        let mut visited = HashSet::from([self.canonical_hash()]);
+        let mut queue = VecDeque::from([self.without_history()]);
+        while let Some(game) = queue.pop_front() {
+            for m in game.moves() {
+                if visited.len() >= limit {
+                    return limit;
+                }
+                let mut next = game.clone();
+                next.pour(m);
+                if visited.insert(next.canonical_hash()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        visited.len().min(limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadlocked_boards_are_reported() {
+        // The 3 is sorted, but the other columns are full and topped with different colors.
+        let p = Puzzle::new(2, &[vec![1, 2], vec![2, 1], vec![3]]);
+        assert!(p.is_deadlocked());
+        assert_eq!(p.best_effort(), (VecDeque::new(), Score::Deadlocked));
+
+        // Unsolvable too, but some move can always be played.
+        let p = Puzzle::new(3, &[vec![1, 2, 1], vec![2, 1, 2], vec![1, 2]]);
+        assert!(!p.is_deadlocked());
+        let (moves, score) = p.best_effort();
+        assert!(!matches!(score, Score::Win | Score::Deadlocked));
+        p.without_history().apply_moves(&Vec::from(moves)).unwrap();
+
+        assert!(!Puzzle::new(2, &[vec![1, 1], vec![2, 2]]).is_deadlocked());
+    }
+
+    #[test]
+    fn complete_columns_make_room_for_taller_colors() {
+        // The 2s only fit together in the tall column, so the complete 1s have to leave it.
+        let p = Puzzle::new(
+            vec![4, 2, 2, 2],
+            &[vec![1, 1], vec![], vec![2, 2], vec![2, 2]],
+        );
+        let bfs = p.solve_bfs().unwrap();
+        assert_eq!(bfs.len(), 3);
+        assert_eq!(p.solve_astar().map(|m| m.len()), Some(3));
+        assert_eq!(p.verify_solution(&Vec::from(bfs)), Ok(()));
+        assert_ne!(p.difficulty(), crate::Difficulty::BrutalUnsolvable);
+    }
+
+    #[test]
+    fn best_effort_wins_in_the_fewest_moves() {
+        let p = Puzzle::new(2, &[vec![1, 2], vec![2, 1], vec![]]);
+        let (moves, score) = p.best_effort();
+        assert_eq!(score, Score::Win);
+        assert_eq!(moves.len(), p.solve_bfs().unwrap().len());
+        p.verify_solution(&Vec::from(moves)).unwrap();
+
+        let solved = Puzzle::new(2, &[vec![1, 1], vec![]]);
+        assert_eq!(solved.best_effort(), (VecDeque::new(), Score::Win));
+    }
+
+    #[test]
+    fn counts_the_boards_of_a_tiny_puzzle() {
+        // Either top ball goes to the empty column, then the balls of the other color gather,
+        // then those of the first one: the two lines meet on the solved board and can't go back.
+        let p = Puzzle::new(2, &[vec![1, 2], vec![2, 1], vec![]]);
+        assert_eq!(p.count_reachable_states(usize::MAX), 6);
+        assert_eq!(p.count_reachable_states(4), 4);
+        assert_eq!(
+            Puzzle::new(1, &[vec![1], vec![]]).count_reachable_states(10),
+            1
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::heuristic;
+use crate::{Move, Puzzle, SearchColor};
+
+/// Returns whether `a` and `b` touch different columns, in which case they can be played in
+/// either order with the same result.
+fn independent(a: Move, b: Move) -> bool {
+    ![b.from, b.to].contains(&a.from) && ![b.from, b.to].contains(&a.to)
+}
+
+/// Returns whether playing `m` after `path` gives the smallest of the orderings of the
+/// independent moves at its end, that is whether `m` can't be swapped with a greater move
+/// played after the last move it depends on.
+fn in_normal_form(path: &[Move], m: Move) -> bool {
+    for &p in path.iter().rev() {
+        if !independent(p, m) {
+            return true;
+        }
+        if (m.from, m.to) < (p.from, p.to) {
+            return false;
+        }
+    }
+    true
+}
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Returns the winning lines of at most `max_len` moves, stopping after `max_count` of them.
+    /// Lines only differing by the order of moves on different columns, which reach the same
+    /// boards, are returned once, and lines going back to a board they already went through are
+    /// left out.
+    pub fn solutions_up_to(&self, max_len: usize, max_count: usize) -> Vec<VecDeque<Move>> {
+        let mut game = self.without_history();
+        let mut solutions = Vec::new();
+        let mut on_path = HashSet::from([game.canonical_hash()]);
+        game.enumerate(
+            max_len,
+            max_count,
+            &mut Vec::new(),
+            &mut on_path,
+            &mut solutions,
+        );
+        solutions
+    }
+
+    /// Adds to `solutions` the winning lines extending `path` within `max_len` moves.
+    fn enumerate(
+        &mut self,
+        max_len: usize,
+        max_count: usize,
+        path: &mut Vec<Move>,
+        on_path: &mut HashSet<u64>,
+        solutions: &mut Vec<VecDeque<Move>>,
+    ) {
+        if self.is_solved() {
+            solutions.push(path.iter().copied().collect());
+            return;
+        }
+        if path.len() + heuristic(self) > max_len {
+            return;
+        }
+
+        for m in self.moves().collect::<Vec<_>>() {
+            if solutions.len() >= max_count {
+                return;
+            }
+            if !in_normal_form(path, m) {
+                continue;
+            }
+            let count = self.pour(m);
+            let hash = self.canonical_hash();
+            if on_path.insert(hash) {
+                path.push(m);
+                self.enumerate(max_len, max_count, path, on_path, solutions);
+                path.pop();
+                on_path.remove(&hash);
+            }
+            self.undo_move(m, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_distinct_solutions() {
+        // Either 2 is moved first to the empty column, then the other, and either column of 1s
+        // is poured onto the other one.
+        let p = Puzzle::new(2, &[vec![1, 2], vec![1, 2], vec![]]);
+        let solutions = p.solutions_up_to(3, 100);
+        assert_eq!(solutions.len(), 4);
+        for moves in solutions {
+            assert_eq!(moves.len(), 3);
+            assert_eq!(p.verify_solution(&Vec::from(moves)), Ok(()));
+        }
+        assert_eq!(p.solutions_up_to(3, 2).len(), 2);
+        assert!(p.solutions_up_to(2, 100).is_empty());
+    }
+}
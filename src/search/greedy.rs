@@ -0,0 +1,116 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::zobrist::ZobristHash;
+use crate::{Capacity, Column, Move, Puzzle, SearchColor};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Finds a solution quickly, with no guarantee on its length: the move reaching the best
+    /// ranked board not visited yet is played until the puzzle is solved. When every move leads
+    /// to a board already visited, the last move is taken back and the next best move of the
+    /// previous board is tried. Returns `None` if all the reachable boards were visited without
+    /// winning.
+    pub fn solve_greedy(&self) -> Option<VecDeque<Move>> {
+        let mut game = self.without_history();
+        let mut visited = HashSet::from([game.canonical_hash()]);
+        let mut path = VecDeque::new();
+        while !game.is_solved() {
+            let mut best = None;
+            for m in game.moves().collect::<Vec<_>>() {
+                let count = game.pour(m);
+                if !visited.contains(&game.canonical_hash()) {
+                    let score = game.rank();
+                    if best.is_none_or(|(s, _)| score > s) {
+                        best = Some((score, m));
+                    }
+                }
+                game.undo_move(m, count);
+            }
+            match best {
+                Some((_, m)) => {
+                    game.pour(m);
+                    visited.insert(game.canonical_hash());
+                    path.push_back(m);
+                }
+                None => {
+                    let m = path.pop_back()?;
+                    game.undo_move(m, m.count);
+                }
+            }
+        }
+        Some(path)
+    }
+
+    /// Returns whether the puzzle can be solved. This runs [`Puzzle::solve_greedy`], which
+    /// usually finds a win quickly when there is one, but has to visit every reachable board to
+    /// tell that there is none.
+    pub fn is_solvable(&self) -> bool {
+        self.solve_greedy().is_some()
+    }
+
+    /// Returns the fewest empty columns to add to the board, as tall as its tallest column, for
+    /// it to become solvable according to [`Puzzle::is_solvable`], 0 if it already is. With one
+    /// empty column per color every ball can be poured straight onto its color, so `None` is
+    /// returned if even that doesn't do, which happens when a color has more balls than any
+    /// column can hold.
+    pub fn min_empties_to_solve(&self) -> Option<usize> {
+        let size = self.capacity.max();
+        if self.colors_count.values().any(|&n| n > size) {
+            return None;
+        }
+        let mut board = self.without_history();
+        for empties in 0..=self.num_colors() {
+            if empties > 0 {
+                board.state.push(Column::new());
+                if let Capacity::PerColumn(sizes) = &mut board.capacity {
+                    sizes.push(size);
+                }
+                board.zobrist = ZobristHash::new(&board.state, &board.capacity);
+            }
+            if board.is_solvable() {
+                return Some(empties);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_solvable_boards() {
+        assert!(Puzzle::new(2, &[vec![1, 2], vec![1, 2], vec![]]).is_solvable());
+        assert!(Puzzle::new(2, &[vec![1, 1], vec![2, 2]]).is_solvable());
+        // Both columns are full and topped with different colors: no move can be played.
+        assert!(!Puzzle::new(2, &[vec![1, 2], vec![2, 1]]).is_solvable());
+    }
+
+    #[test]
+    fn counts_the_empty_columns_needed() {
+        // Both columns are full and topped with different colors until one empty column is added.
+        assert_eq!(
+            Puzzle::new(2, &[vec![0, 1], vec![1, 0]]).min_empties_to_solve(),
+            Some(1)
+        );
+        assert_eq!(
+            Puzzle::new(2, &[vec![0, 0], vec![1, 1]]).min_empties_to_solve(),
+            Some(0)
+        );
+        // Three balls of color 0 can't fit in a column of two.
+        assert_eq!(
+            Puzzle::new(2, &[vec![0, 0], vec![0, 1], vec![1]]).min_empties_to_solve(),
+            None
+        );
+    }
+
+    #[test]
+    fn complete_columns_of_mixed_sizes_stay_movable() {
+        let p = Puzzle::new(
+            vec![4, 2, 2, 2],
+            &[vec![1, 1], vec![], vec![2, 2], vec![2, 2]],
+        );
+        assert!(p.is_solvable());
+        assert_eq!(p.min_empties_to_solve(), Some(0));
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::heuristic;
+use crate::{Move, Puzzle, SearchColor};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Finds a shortest solution with an IDA* search, or returns `None` if the puzzle cannot be
+    /// solved. Depth-first searches are repeated with a growing bound on the number of moves
+    /// played plus the heuristic of [`Puzzle::solve_astar`], so the memory used only grows with
+    /// the length of the solution, at the cost of exploring boards several times.
+    pub fn solve_ida(&self) -> Option<VecDeque<Move>> {
+        let mut game = self.without_history();
+        let mut path = VecDeque::new();
+        let mut on_path = HashSet::from([game.canonical_hash()]);
+        let mut bound = heuristic(&game);
+        loop {
+            match game.ida(0, bound, &mut path, &mut on_path) {
+                Ok(()) => return Some(path),
+                // Every line runs into a board already on its path.
+                Err(None) => return None,
+                Err(Some(next)) => bound = next,
+            }
+        }
+    }
+
+    /// Searches the lines of at most `bound` moves, estimated with the heuristic, from the board
+    /// reached after `g` moves. On success `path` holds the winning moves. Otherwise, returns
+    /// the smallest estimate that went over the bound, if any.
+    fn ida(
+        &mut self,
+        g: usize,
+        bound: usize,
+        path: &mut VecDeque<Move>,
+        on_path: &mut HashSet<u64>,
+    ) -> Result<(), Option<usize>> {
+        let f = g + heuristic(self);
+        if f > bound {
+            return Err(Some(f));
+        }
+        if self.is_solved() {
+            return Ok(());
+        }
+
+        let mut next_bound = None;
+        for m in self.moves().collect::<Vec<_>>() {
+            let count = self.pour(m);
+            let hash = self.canonical_hash();
+            let result = if on_path.insert(hash) {
+                path.push_back(m);
+                let result = self.ida(g + 1, bound, path, on_path);
+                if result.is_err() {
+                    path.pop_back();
+                }
+                on_path.remove(&hash);
+                result
+            } else {
+                Err(None)
+            };
+            self.undo_move(m, count);
+            match result {
+                Ok(()) => return Ok(()),
+                Err(Some(f)) => next_bound = Some(next_bound.map_or(f, |b: usize| b.min(f))),
+                Err(None) => {}
+            }
+        }
+        Err(next_bound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::small_boards;
+
+    #[test]
+    fn ida_is_as_short_as_bfs() {
+        for (seed, p, shortest) in small_boards() {
+            let ida = p.solve_ida().unwrap();
+            assert_eq!(ida.len(), shortest, "seed {seed}");
+            assert_eq!(p.verify_solution(&Vec::from(ida)), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+use std::num::NonZero;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::{Move, Puzzle, Score, SearchColor, SearchContext, Split};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Same as [`Puzzle::solve`], but with the moves from the root of each search explored on
+    /// several threads, up to the available parallelism. This returns the same moves as `solve`.
+    pub fn solve_parallel(&self, max_depth: u32, iterations: u32) -> VecDeque<Move> {
+        self.iddfs(
+            max_depth,
+            iterations,
+            &mut SearchContext {
+                split: Split::Threads,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as `dfs`, with each move from the root searched by a pool of threads.
+    pub(crate) fn dfs_parallel(&self, depth: u32, score: Score) -> (Score, VecDeque<Move>) {
+        if depth == 0 {
+            return (score, VecDeque::new());
+        }
+
+        let moves: Vec<Move> = self.moves().collect();
+        let threads = thread::available_parallelism()
+            .map_or(1, NonZero::get)
+            .min(moves.len());
+        let next = AtomicUsize::new(0);
+        // Index of the first move found to win. The sequential search would stop there, so the
+        // moves after it can be abandoned, while the ones before it still have to be searched to
+        // pick the same move regardless of which thread finishes first.
+        let first_win = AtomicUsize::new(usize::MAX);
+        let mut results = vec![None; moves.len()];
+        thread::scope(|s| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut game = self.without_history();
+                        let mut done = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            if i >= moves.len() || i > first_win.load(Ordering::Relaxed) {
+                                return done;
+                            }
+                            let stop = || first_win.load(Ordering::Relaxed) < i;
+                            let mut ctx = SearchContext {
+                                stop: Some(&stop),
+                                ..Default::default()
+                            };
+                            let count = game.pour(moves[i]);
+                            let result = game.dfs(depth - 1, game.rank(), &mut ctx);
+                            game.undo_move(moves[i], count);
+                            if let Score::Win = result.0 {
+                                first_win.fetch_min(i, Ordering::Relaxed);
+                            }
+                            done.push((i, result));
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                for (i, result) in worker.join().expect("search thread panicked") {
+                    results[i] = Some(result);
+                }
+            }
+        });
+
+        // Pick the move the sequential search would have picked.
+        let mut best_score = score;
+        let mut best_moves = VecDeque::new();
+        for (&m, result) in moves.iter().zip(results) {
+            let (child_score, mut moves) =
+                result.expect("moves before the first win are always searched");
+            if child_score > best_score {
+                best_score = child_score;
+                moves.push_front(m);
+                best_moves = moves;
+
+                if let Score::Win = child_score {
+                    break;
+                }
+            }
+        }
+
+        (best_score, best_moves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::main_board;
+
+    #[test]
+    fn parallel_search_finds_the_same_moves() {
+        let p = main_board();
+        let moves = p.solve_parallel(5, 100);
+        assert_eq!(moves, p.solve(5, 100));
+        assert_eq!(p.verify_solution(&Vec::from(moves)), Ok(()));
+    }
+}
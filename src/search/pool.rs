@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+
+use crate::{Move, Puzzle, Score, SearchColor, SearchContext, Split};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Same as [`Puzzle::solve_parallel`], with the moves from the root of each search handed to
+    /// the rayon thread pool instead of threads of its own. This also returns the same moves as
+    /// [`Puzzle::solve`].
+    pub fn solve_rayon(&self, max_depth: u32, iterations: u32) -> VecDeque<Move> {
+        self.iddfs(
+            max_depth,
+            iterations,
+            &mut SearchContext {
+                split: Split::Rayon,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as `dfs`, with the subtree of each move searched as a rayon task. The best line found
+    /// so far is shared by all the tasks, ties going to the earliest move as in the sequential
+    /// search, so the result doesn't depend on which task finishes first.
+    pub(crate) fn dfs_rayon(&self, depth: u32, score: Score) -> (Score, VecDeque<Move>) {
+        if depth == 0 {
+            return (score, VecDeque::new());
+        }
+
+        let moves: Vec<Move> = self.moves().collect();
+        // Index of the first move found to win. Rather than a flag telling that some move wins,
+        // this lets the moves before it finish, as one of them may win too and would be the one
+        // picked by the sequential search.
+        let first_win = AtomicUsize::new(usize::MAX);
+        let best: Mutex<Option<(Score, usize, VecDeque<Move>)>> = Mutex::new(None);
+        moves.par_iter().enumerate().for_each(|(i, &m)| {
+            let stop = || first_win.load(Ordering::Relaxed) < i;
+            if stop() {
+                return;
+            }
+            let mut ctx = SearchContext {
+                stop: Some(&stop),
+                ..Default::default()
+            };
+            let mut game = self.without_history();
+            game.pour(m);
+            let (child_score, mut line) = game.dfs(depth - 1, game.rank(), &mut ctx);
+            if let Score::Win = child_score {
+                first_win.fetch_min(i, Ordering::Relaxed);
+            }
+            if stop() {
+                // Interrupted, or beaten by an earlier win anyway.
+                return;
+            }
+            line.push_front(m);
+            let mut best = best.lock().expect("search task panicked");
+            if best
+                .as_ref()
+                .is_none_or(|&(s, j, _)| child_score > s || (child_score == s && i < j))
+            {
+                *best = Some((child_score, i, line));
+            }
+        });
+
+        match best.into_inner().expect("search task panicked") {
+            Some((child_score, _, line)) if child_score > score => (child_score, line),
+            _ => (score, VecDeque::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rayon_search_matches_the_sequential_one() {
+        for p in [
+            crate::tests::main_board(),
+            Puzzle::generate_seeded(4, 8, 2, 68),
+        ] {
+            let moves = p.solve(5, 100);
+            for _ in 0..3 {
+                assert_eq!(p.solve_rayon(5, 100), moves);
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::{Move, Puzzle, SearchColor};
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Number of moves after which a rollout gives up.
+    const ROLLOUT_STEPS: usize = 1000;
+
+    /// Finds a solution on boards too large for the other searches by playing `rollouts` games
+    /// of random moves, returning the shortest one that wins, or `None` if none does. A rollout
+    /// never goes back to a board it already went through, and gives up once it gets stuck or
+    /// as long as the best win so far. Pass a seeded `rng` to get the same result on every run.
+    pub fn solve_mcts<R: Rng + ?Sized>(
+        &self,
+        rollouts: usize,
+        rng: &mut R,
+    ) -> Option<VecDeque<Move>> {
+        if self.is_solved() {
+            return Some(VecDeque::new());
+        }
+
+        let mut best: Option<VecDeque<Move>> = None;
+        for _ in 0..rollouts {
+            let max_steps = best.as_ref().map_or(Self::ROLLOUT_STEPS, |b| b.len() - 1);
+            let mut game = self.without_history();
+            let mut visited = HashSet::from([game.canonical_hash()]);
+            let mut path = VecDeque::new();
+            while path.len() < max_steps {
+                let moves: Vec<Move> = game.moves().collect();
+                let moves: Vec<Move> = moves
+                    .into_iter()
+                    .filter(|&m| {
+                        let count = game.pour(m);
+                        let new = !visited.contains(&game.canonical_hash());
+                        game.undo_move(m, count);
+                        new
+                    })
+                    .collect();
+                let Some(&m) = moves.choose(rng) else {
+                    break;
+                };
+                game.pour(m);
+                visited.insert(game.canonical_hash());
+                path.push_back(m);
+                if game.is_solved() {
+                    best = Some(path);
+                    break;
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::ChaCha8Rng;
+
+    use super::*;
+
+    #[test]
+    fn rollouts_solve_generated_boards() {
+        let p = Puzzle::generate_seeded(4, 6, 2, 83);
+        let moves = p
+            .solve_mcts(50, &mut ChaCha8Rng::seed_from_u64(83))
+            .unwrap();
+        assert_eq!(p.verify_solution(&Vec::from(moves.clone())), Ok(()));
+        let again = p.solve_mcts(50, &mut ChaCha8Rng::seed_from_u64(83));
+        assert_eq!(again, Some(moves));
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use crate::{Move, Puzzle, PuzzleError, Score};
+
+/// Move of a solution along with what it achieves, returned by [`Puzzle::annotate_solution`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AnnotatedMove<C> {
+    /// The move, with the number of balls it actually poured.
+    pub step: Move,
+    /// Rank of the board after the move.
+    pub rank: Score,
+    /// Color whose balls the move gathered all in the destination, if any.
+    pub completed: Option<C>,
+    /// Whether the move emptied its source column.
+    pub emptied: bool,
+}
+
+impl<C: Copy + Eq + Hash> Puzzle<C> {
+    /// Replays `moves` until the board is solved, returning the moves played with the number of
+    /// balls they actually poured, or `None` if a move is illegal or the board is never solved.
+    fn replay_winning(&self, moves: &[Move]) -> Option<Vec<Move>> {
+        let mut p = self.without_history();
+        let mut played = Vec::new();
+        for &m in moves {
+            if p.is_solved() {
+                break;
+            }
+            let count = p.do_move(m).ok()?;
+            played.push(Move { count, ..m });
+        }
+        p.is_solved().then_some(played)
+    }
+
+    /// Replays `moves`, returning each of them with the rank of the board it leads to and
+    /// whether it completed a color or emptied a column, e.g. to print a readable walkthrough.
+    /// Fails with [`PuzzleError::IllegalStep`] on the first move that cannot be played.
+    pub fn annotate_solution(&self, moves: &[Move]) -> Result<Vec<AnnotatedMove<C>>, PuzzleError> {
+        let mut p = self.without_history();
+        let mut annotated = Vec::new();
+        for (index, &m) in moves.iter().enumerate() {
+            let was_complete = m.to < p.state.len() && p.is_complete(m.to);
+            let count = p.do_move(m).map_err(|error| PuzzleError::IllegalStep {
+                index,
+                error: Box::new(error),
+            })?;
+            let completed = (!was_complete && p.is_complete(m.to))
+                .then(|| p.single_color(&p.state[m.to]))
+                .flatten();
+            annotated.push(AnnotatedMove {
+                step: Move { count, ..m },
+                rank: p.rank(),
+                completed,
+                emptied: p.state[m.from].is_empty(),
+            });
+        }
+        Ok(annotated)
+    }
+
+    /// Shortens a winning solution by dropping the moves it can do without, such as detours that
+    /// pour balls out of a column and later back into it. Stretches of moves are removed, longest
+    /// first, as long as replaying what is left still wins, so the result is always a winning
+    /// solution. Moves that aren't a winning solution are returned as they are.
+    pub fn simplify_solution(&self, moves: &[Move]) -> VecDeque<Move> {
+        let Some(mut best) = self.replay_winning(moves) else {
+            return moves.iter().copied().collect();
+        };
+        'shorten: loop {
+            for len in (1..=best.len()).rev() {
+                for start in 0..=best.len() - len {
+                    let candidate = [&best[..start], &best[start + len..]].concat();
+                    if let Some(shorter) = self.replay_winning(&candidate) {
+                        best = shorter;
+                        continue 'shorten;
+                    }
+                }
+            }
+            return best.into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn simplify_drops_the_detours() {
+        let p = Puzzle::new(3, &[vec![1, 2], vec![1, 2], vec![]]);
+        let mv = crate::tests::mv;
+        // The 2 poured onto the other one is poured back with it right away.
+        let padded = [mv(0, 2), mv(2, 1), mv(1, 2), mv(0, 1)];
+        assert_eq!(p.verify_solution(&padded), Ok(()));
+        let simplified = Vec::from(p.simplify_solution(&padded));
+        assert_eq!(simplified.len(), 3);
+        assert_eq!(p.verify_solution(&simplified), Ok(()));
+    }
+
+    #[test]
+    fn annotates_a_short_solution() {
+        let p = Puzzle::new(2, &[vec![1, 2], vec![1, 2], vec![]]);
+        let mv = crate::tests::mv;
+        let annotated = p
+            .annotate_solution(&[mv(0, 2), mv(1, 2), mv(0, 1)])
+            .unwrap();
+        let summary: Vec<(usize, Option<Color>, bool)> = annotated
+            .iter()
+            .map(|a| (a.step.count, a.completed, a.emptied))
+            .collect();
+        assert_eq!(
+            summary,
+            [(1, None, false), (1, Some(2), false), (1, Some(1), true)]
+        );
+        assert_eq!(annotated[2].rank, Score::Win);
+        assert!(annotated[..2].iter().all(|a| a.rank != Score::Win));
+
+        assert!(matches!(
+            p.annotate_solution(&[mv(0, 2), mv(2, 0)]),
+            Err(PuzzleError::IllegalStep { index: 1, .. })
+        ));
+    }
+}
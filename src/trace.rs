@@ -0,0 +1,119 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write;
+
+use crate::{Move, Puzzle, Score, SearchColor, SearchContext};
+
+/// Boards and moves explored by a search, recorded by [`Puzzle::solve_trace`]. Boards are
+/// identified by the hash of their canonical form, so a board reached through several lines
+/// appears once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchTrace {
+    /// Boards in the order they were first reached, with their score.
+    pub nodes: Vec<(u64, Score)>,
+    /// Moves played during the search, from the hash of a board to the hash of the next one.
+    pub edges: Vec<(u64, u64, Move)>,
+    /// Whether the search explored more than `limit` boards or moves, the rest being dropped.
+    pub truncated: bool,
+    limit: usize,
+    seen_nodes: HashSet<u64>,
+    seen_edges: HashSet<(u64, u64)>,
+}
+
+impl SearchTrace {
+    fn new(limit: usize) -> Self {
+        SearchTrace {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// Records that the search played `m` from the board `from` to the board `to`, unless that
+    /// would go over the limit.
+    pub(crate) fn record(&mut self, from: (u64, Score), to: (u64, Score), m: Move) {
+        if self.seen_edges.contains(&(from.0, to.0)) {
+            return;
+        }
+        let new_nodes = [from.0, to.0]
+            .iter()
+            .filter(|hash| !self.seen_nodes.contains(hash))
+            .count();
+        if self.edges.len() >= self.limit || self.nodes.len() + new_nodes > self.limit {
+            self.truncated = true;
+            return;
+        }
+        for (hash, score) in [from, to] {
+            if self.seen_nodes.insert(hash) {
+                self.nodes.push((hash, score));
+            }
+        }
+        self.seen_edges.insert((from.0, to.0));
+        self.edges.push((from.0, to.0, m));
+    }
+
+    /// Renders the trace as a GraphViz digraph, with boards labeled with their score and moves
+    /// labeled like they are displayed. Winning boards are highlighted.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n");
+        for &(hash, score) in &self.nodes {
+            let _ = match score {
+                Score::Deadlocked => writeln!(dot, "  n{hash:016x} [label=\"deadlocked\"];"),
+                Score::Score(s) => writeln!(dot, "  n{hash:016x} [label=\"{s}\"];"),
+                Score::Win => writeln!(dot, "  n{hash:016x} [label=\"win\", style=filled];"),
+            };
+        }
+        for &(from, to, m) in &self.edges {
+            let _ = writeln!(dot, "  n{from:016x} -> n{to:016x} [label=\"{m}\"];");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<C: SearchColor> Puzzle<C> {
+    /// Same as [`Puzzle::solve`], also recording the boards and moves explored, up to `limit` of
+    /// each, e.g. to render them with [`SearchTrace::to_dot`] and see where the search spends
+    /// its effort.
+    pub fn solve_trace(
+        &self,
+        max_depth: u32,
+        iterations: u32,
+        limit: usize,
+    ) -> (VecDeque<Move>, SearchTrace) {
+        let mut trace = SearchTrace::new(limit);
+        let moves = self.iddfs(
+            max_depth,
+            iterations,
+            &mut SearchContext {
+                trace: Some(&mut trace),
+                ..Default::default()
+            },
+        );
+        (moves, trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_link_the_boards_searched() {
+        let p = Puzzle::new(2, &[vec![1, 2], vec![2, 1], vec![]]);
+        let (moves, trace) = p.solve_trace(5, 10, 1000);
+        assert_eq!(moves, p.solve(5, 10));
+        assert!(!trace.truncated);
+        assert_eq!(trace.nodes[0].0, p.state_hash());
+        assert!(trace.nodes.iter().any(|&(_, score)| score == Score::Win));
+        for &(from, to, _) in &trace.edges {
+            assert!(trace.nodes.iter().any(|&(hash, _)| hash == from));
+            assert!(trace.nodes.iter().any(|&(hash, _)| hash == to));
+        }
+        let dot = trace.to_dot();
+        assert!(dot.starts_with("digraph search {\n"), "{dot}");
+        assert!(dot.contains("style=filled"), "{dot}");
+
+        let (_, cut) = p.solve_trace(5, 10, 2);
+        assert!(cut.truncated);
+        assert!(cut.nodes.len() <= 2 && cut.edges.len() <= 2);
+    }
+}
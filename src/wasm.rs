@@ -0,0 +1,48 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Move, Puzzle};
+
+/// Result of [`solve`], handed to JavaScript as JSON.
+#[derive(Serialize)]
+struct WasmSolution {
+    moves: Vec<Move>,
+    /// Whether the moves win, as the solver may run out of iterations before.
+    solved: bool,
+}
+
+/// Checks that `board` is a JSON board in the shape read by [`Puzzle::from_json`], throwing the
+/// reason why it isn't otherwise.
+#[wasm_bindgen(js_name = validateBoard)]
+pub fn validate_board(board: &str) -> Result<(), String> {
+    Puzzle::from_json(board)
+        .map(drop)
+        .map_err(|e| e.to_string())
+}
+
+/// Solves a JSON board with [`Puzzle::solve`] and returns the moves as JSON shaped like
+/// `{ "moves": [{ "from": .., "to": .., "count": .. }], "solved": true }`. Throws an error
+/// message instead if the board cannot be read.
+#[wasm_bindgen]
+pub fn solve(board: &str, max_depth: u32, iterations: u32) -> Result<String, String> {
+    let p = Puzzle::from_json(board).map_err(|e| e.to_string())?;
+    let moves: Vec<Move> = p.solve(max_depth, iterations).into();
+    let solved = p.verify_solution(&moves).is_ok();
+    serde_json::to_string(&WasmSolution { moves, solved }).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_huge_column_sizes() {
+        let board = r#"{"column_size": 100000000000000000, "columns": [[1]]}"#;
+        assert_eq!(validate_board(board), Ok(()));
+        let moves = solve(board, 4, 10).unwrap();
+        assert_eq!(moves, r#"{"moves":[],"solved":true}"#);
+
+        let board = r#"{"column_size": 100000000000000000, "columns": [[1, 2], [2, 1], []]}"#;
+        assert!(solve(board, 4, 10).unwrap().ends_with(r#""solved":true}"#));
+    }
+}
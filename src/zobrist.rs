@@ -0,0 +1,155 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::{Capacity, Column};
+
+/// Step of the splitmix64 generator, also used to mix column hashes together.
+pub(crate) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Zobrist hash of a board, kept up to date by the moves played on it and returned by
+/// [`Puzzle::state_hash`](crate::Puzzle::state_hash).
+///
+/// A column hashes to the XOR of the keys of its balls, and a board to the sum of its column
+/// hashes mixed with their sizes, so that boards that only differ by the order of their columns
+/// hash the same. Both are updated in time proportional to the number of balls poured.
+///
+/// Colors may be of any type, so rather than a table of random keys indexed by color ids, the
+/// key of a ball is derived from the hash of its color and its height in the column.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ZobristHash {
+    columns: Vec<u64>,
+    value: u64,
+}
+
+/// Returns the key of a ball of color `c` at height `slot` of a column.
+fn key<C: Hash>(slot: usize, c: &C) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    c.hash(&mut hasher);
+    splitmix64(hasher.finish() ^ splitmix64(slot as u64))
+}
+
+/// Returns the contribution to the board hash of a column hashing to `column` and holding up to
+/// `size` balls.
+fn mix(column: u64, size: usize) -> u64 {
+    splitmix64(column.wrapping_add((size as u64).wrapping_mul(0xff51_afd7_ed55_8ccd)))
+}
+
+impl ZobristHash {
+    /// Hashes a board from scratch.
+    pub(crate) fn new<C: Hash>(state: &[Column<C>], capacity: &Capacity) -> Self {
+        let columns: Vec<u64> = state
+            .iter()
+            .map(|col| {
+                col.iter()
+                    .enumerate()
+                    .fold(0, |h, (slot, c)| h ^ key(slot, c))
+            })
+            .collect();
+        let value = columns
+            .iter()
+            .enumerate()
+            .fold(0u64, |v, (i, &h)| v.wrapping_add(mix(h, capacity.of(i))));
+        ZobristHash { columns, value }
+    }
+
+    /// Returns the hash of the whole board.
+    pub(crate) fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Updates the hash for `balls` poured from the top of column `from`, which held `from_len`
+    /// balls, onto column `to`, which held `to_len`. The balls are given as they land on `to`,
+    /// bottom to top, as they may differ from the order they left `from` in when jokers are
+    /// mixed in the run.
+    pub(crate) fn pour<C: Hash>(
+        &mut self,
+        capacity: &Capacity,
+        (from, to): (usize, usize),
+        (from_len, to_len): (usize, usize),
+        balls: &[C],
+    ) {
+        for i in [from, to] {
+            self.value = self
+                .value
+                .wrapping_sub(mix(self.columns[i], capacity.of(i)));
+        }
+        for (k, c) in balls.iter().enumerate() {
+            self.columns[from] ^= key(from_len - 1 - k, c);
+            self.columns[to] ^= key(to_len + k, c);
+        }
+        for i in [from, to] {
+            self.value = self
+                .value
+                .wrapping_add(mix(self.columns[i], capacity.of(i)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::seq::IndexedRandom;
+    use rand::{RngExt, SeedableRng};
+
+    use super::*;
+    use crate::{Move, Puzzle};
+
+    fn rehash(p: &Puzzle) -> u64 {
+        ZobristHash::new(&p.state, &p.capacity).value()
+    }
+
+    #[test]
+    fn incremental_hash_matches_rehashing() {
+        let mut rng = StdRng::seed_from_u64(14);
+        for seed in 0..20 {
+            let mut p = Puzzle::generate_seeded(4, 5, 2, seed);
+            if seed % 2 == 1 {
+                p.set_joker(Some(0));
+            }
+            let mut played = Vec::new();
+            for _ in 0..200 {
+                let moves = p.legal_moves();
+                if moves.is_empty() || !played.is_empty() && rng.random_range(0..4) == 0 {
+                    let Some((m, count)) = played.pop() else {
+                        break;
+                    };
+                    p.undo_move(m, count);
+                } else {
+                    let &m = moves.choose(&mut rng).unwrap();
+                    let count = p.do_move(m).unwrap();
+                    played.push((m, count));
+                }
+                assert_eq!(p.state_hash(), rehash(&p));
+            }
+        }
+    }
+
+    #[test]
+    fn hashes_runs_mixing_jokers() {
+        let mut p = Puzzle::new(4, &[vec![1, 9, 2, 9], vec![]]);
+        p.set_joker(Some(9));
+        let m = Move {
+            from: 0,
+            to: 1,
+            count: 0,
+        };
+        let count = p.do_move(m).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(p.state_hash(), rehash(&p));
+        p.undo_move(m, count);
+        assert_eq!(p.state_hash(), rehash(&p));
+    }
+
+    #[test]
+    fn sizes_are_part_of_the_hash() {
+        let short = Puzzle::new(vec![2, 4], &[vec![1, 1], vec![]]);
+        let tall = Puzzle::new(vec![4, 2], &[vec![1, 1], vec![]]);
+        let swapped = Puzzle::new(vec![4, 2], &[vec![], vec![1, 1]]);
+        assert_ne!(short.state_hash(), tall.state_hash());
+        assert_eq!(short.state_hash(), swapped.state_hash());
+    }
+}